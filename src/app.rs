@@ -1,50 +1,96 @@
 use std::{
     io,
+    sync::Arc,
     thread::sleep,
     time::{Duration, Instant},
 };
 
-use crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind};
+use crossterm::event::{
+    self, Event, KeyCode, KeyEvent, KeyEventKind, MouseButton, MouseEvent, MouseEventKind,
+};
 use ratatui::{
     DefaultTerminal, Frame,
-    layout::{Constraint, Direction, Layout},
-    widgets::Paragraph,
+    layout::{Constraint, Layout, Rect},
 };
 
-use crate::widgets::{
-    counter::Counter, cpu_cores_widget::CoresWidget, fans_widget::FansWidget, logs_widget::Logs,
-    navbar::Navbar, usage_widget::UsageWidget,
+use crate::{
+    fan_curve::CurvesHandle,
+    hardware::Backend,
+    history::HistoryHandle,
+    logs::LogHandle,
+    sampler::{Intervals, Sampler, Snapshot},
+    widgets::{
+        battery_widget::BatteryWidget,
+        cpu_cores_widget::CoresWidget,
+        cpu_power_widget::CpuPowerWidget,
+        fans_widget::FansWidget,
+        logs_widget::Logs,
+        navbar::{Navbar, Page},
+        panel::{Action, Panel},
+        processes_widget::ProcessesWidget,
+        sensors_widget::{SensorPick, SensorsWidget},
+        settings_widget::SettingsWidget,
+        usage_widget::UsageWidget,
+    },
 };
 
-pub struct App<'a> {
+// Panels that can take the focus.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum PanelId {
+    Fans,
+    Logs,
+    Sensors,
+    CpuPower,
+    Battery,
+    Processes,
+}
+
+// in Tab order, the first one is focused when the page is opened
+fn focus_order(page: Page) -> &'static [PanelId] {
+    match page {
+        Page::Dashboard => &[PanelId::Fans, PanelId::Logs],
+        Page::Fans => &[PanelId::Fans],
+        Page::Thermals => &[PanelId::Sensors],
+        Page::Power => &[PanelId::CpuPower, PanelId::Battery],
+        Page::Processes => &[PanelId::Processes],
+        Page::Logs => &[PanelId::Logs],
+        Page::Settings => &[],
+    }
+}
+
+pub struct App {
     pub exit: bool,
+    sampler: Sampler,
+    logs: LogHandle,
+    sensor_errors: Vec<(&'static str, String)>,
 
-    // widgets:
-    pub counter: Counter,
+    // panel that gets the keys the app doesn't use itself
+    focus: Option<PanelId>,
 
+    // widgets:
     pub navbar: Navbar,
+    // where the navbar was last drawn
+    navbar_area: Rect,
     pub fans_widget: FansWidget,
     pub cores_widget: CoresWidget,
 
     pub usage_widget: UsageWidget,
-    pub logs_widget: Logs<'a>,
+    pub logs_widget: Logs,
+    pub processes_widget: ProcessesWidget,
+    pub settings_widget: SettingsWidget,
+    pub sensors_widget: SensorsWidget,
+    pub cpu_power_widget: CpuPowerWidget,
+    pub battery_widget: BatteryWidget,
 }
 
-impl App<'_> {
+impl App {
     pub fn run(&mut self, terminal: &mut DefaultTerminal) -> io::Result<()> {
         let mut last_update = Instant::now();
 
-        let mut last_cpu_update = Instant::now();
-
-        self.cores_widget.update();
-        self.usage_widget.update_cpu_data();
-
         while !self.exit {
-            let now = Instant::now();
-            if last_cpu_update.elapsed() >= Duration::from_millis(200) {
-                self.cores_widget.update();
-                self.usage_widget.update_cpu_data();
-                last_cpu_update = now;
+            // draws only ever look at the latest snapshot
+            if let Some(snapshot) = self.sampler.latest() {
+                self.update(snapshot);
             }
 
             // Always redraw every 1000 / 30 = 33 milliseconds
@@ -62,6 +108,29 @@ impl App<'_> {
         Ok(())
     }
 
+    fn update(&mut self, snapshot: Arc<Snapshot>) {
+        self.navbar.update(&snapshot);
+        self.fans_widget.update(&snapshot);
+        self.cores_widget.update(&snapshot);
+        self.usage_widget.update(&snapshot);
+        self.processes_widget.update(&snapshot);
+        self.settings_widget.update(&snapshot);
+        self.sensors_widget.update(&snapshot);
+        self.cpu_power_widget.update(&snapshot);
+        self.battery_widget.update(&snapshot);
+
+        // only log an error the first time it shows up, not every tick
+        let errors = snapshot.errors();
+
+        for err in &errors {
+            if !self.sensor_errors.contains(err) {
+                let (source, message) = err;
+                self.logs.warn(source, message.clone());
+            }
+        }
+        self.sensor_errors = errors;
+    }
+
     fn draw(&mut self, frame: &mut Frame) {
         self.render(frame);
     }
@@ -71,48 +140,177 @@ impl App<'_> {
             Event::Key(key_event) if key_event.kind == KeyEventKind::Press => {
                 self.handle_key_event(key_event)
             }
+            Event::Mouse(mouse_event) => self.handle_mouse_event(mouse_event),
             _ => {}
         };
         Ok(())
     }
 
     fn handle_key_event(&mut self, key_event: KeyEvent) {
+        let captured = self
+            .focus
+            .is_some_and(|focus| self.panel_mut(focus).captures_keys());
+
+        // digits switch pages, unless a panel is taking typed input
+        if !captured
+            && let KeyCode::Char(c) = key_event.code
+            && let Some(page) = Page::from_key(c)
+        {
+            self.set_page(page);
+            return;
+        }
+
         match (key_event.code, key_event.modifiers) {
+            // typed input gets every key, q included
+            _ if captured => self.send_to_focus(key_event),
             (KeyCode::Char('q'), _) => self.exit(),
-            (KeyCode::Up, _) => self.counter.increase_counter(),
-            (KeyCode::Down, _) => self.counter.decrease_counter(),
-            _ => {}
+            (KeyCode::Tab, _) => self.cycle_focus(true),
+            (KeyCode::BackTab, _) => self.cycle_focus(false),
+            (KeyCode::Char('['), _) => self.set_page(self.navbar.page.prev()),
+            (KeyCode::Char(']'), _) => self.set_page(self.navbar.page.next()),
+            (KeyCode::Char('p'), _) => self.navbar.cycle_profile(),
+            // zoom every chart out, then back in
+            (KeyCode::Char('z'), _) => {
+                self.usage_widget.window = self.usage_widget.window.next();
+            }
+            // shortcuts to a panel, on whichever page has it
+            (KeyCode::Char('f'), _) => match self.navbar.page {
+                Page::Dashboard => self.set_focus(Some(PanelId::Fans)),
+                _ => self.set_page(Page::Fans),
+            },
+            (KeyCode::Char('t'), _) => self.set_page(Page::Thermals),
+            (KeyCode::Char('g'), _) => {
+                self.set_page(Page::Power);
+                self.set_focus(Some(PanelId::CpuPower));
+            }
+            (KeyCode::Char('b'), _) => {
+                self.set_page(Page::Power);
+                self.set_focus(Some(PanelId::Battery));
+            }
+            _ => self.send_to_focus(key_event),
         }
     }
 
-    fn render(&mut self, frame: &mut Frame) {
-        let main_layout = Layout::default()
-            .direction(Direction::Horizontal)
-            .constraints(vec![Constraint::Fill(2), Constraint::Fill(1)])
-            .split(frame.area());
+    fn send_to_focus(&mut self, key_event: KeyEvent) {
+        let Some(focus) = self.focus else {
+            return;
+        };
+
+        for action in self.panel_mut(focus).handle_key_event(key_event) {
+            match action {
+                Action::PickSensor(SensorPick::Auto) => {
+                    self.sampler.set_cpu_sensor(None);
+                    self.logs
+                        .info("sensors", "cpu temperature: picking a sensor automatically");
+                }
+                Action::PickSensor(SensorPick::Sensor(sensor)) => {
+                    self.sampler.set_cpu_sensor(Some(sensor.id.clone()));
+                    self.logs.info(
+                        "sensors",
+                        format!("cpu temperature: using {}", sensor.name()),
+                    );
+                }
+            }
+        }
+    }
 
-        let (left_col, right_col) = (main_layout[0], main_layout[1]);
+    fn panel_mut(&mut self, panel: PanelId) -> &mut dyn Panel {
+        match panel {
+            PanelId::Fans => &mut self.fans_widget,
+            PanelId::Logs => &mut self.logs_widget,
+            PanelId::Sensors => &mut self.sensors_widget,
+            PanelId::CpuPower => &mut self.cpu_power_widget,
+            PanelId::Battery => &mut self.battery_widget,
+            PanelId::Processes => &mut self.processes_widget,
+        }
+    }
 
-        let left_col_layout = Layout::default()
-            .direction(Direction::Vertical)
-            .constraints(vec![
-                Constraint::Length(3),
-                Constraint::Fill(1),
-                Constraint::Length(10),
-            ])
-            .split(left_col);
+    fn set_focus(&mut self, focus: Option<PanelId>) {
+        if let Some(old) = self.focus {
+            self.panel_mut(old).set_focused(false);
+        }
+        if let Some(new) = focus {
+            self.panel_mut(new).set_focused(true);
+        }
+        self.focus = focus;
+    }
 
-        let (navbar_area, fanwidget_area, core_usage_area) =
-            (left_col_layout[0], left_col_layout[1], left_col_layout[2]);
+    // Tab / Shift-Tab through the focusable panels of the current page
+    fn cycle_focus(&mut self, forward: bool) {
+        let panels = focus_order(self.navbar.page);
+        if panels.is_empty() {
+            return;
+        }
 
-        let right_col_layout = Layout::default()
-            .direction(Direction::Vertical)
-            .constraints(vec![Constraint::Fill(3), Constraint::Fill(2)])
-            .split(right_col);
+        let current = self
+            .focus
+            .and_then(|focus| panels.iter().position(|panel| *panel == focus));
+        let next = match (current, forward) {
+            (Some(idx), true) => (idx + 1) % panels.len(),
+            (Some(idx), false) => (idx + panels.len() - 1) % panels.len(),
+            (None, _) => 0,
+        };
+        self.set_focus(Some(panels[next]));
+    }
 
-        let (heat_area, logs_area) = (right_col_layout[0], right_col_layout[1]);
+    fn handle_mouse_event(&mut self, mouse_event: MouseEvent) {
+        if mouse_event.kind != MouseEventKind::Down(MouseButton::Left) {
+            return;
+        }
+
+        if let Some(page) = Navbar::tab_at(self.navbar_area, mouse_event.column, mouse_event.row) {
+            self.set_page(page);
+        }
+    }
 
+    fn render(&mut self, frame: &mut Frame) {
+        let [navbar_area, page_area] =
+            Layout::vertical([Constraint::Length(3), Constraint::Fill(1)]).areas(frame.area());
+
+        // kept for mouse clicks on the tabs
+        self.navbar_area = navbar_area;
         frame.render_widget(&self.navbar, navbar_area);
+
+        match self.navbar.page {
+            Page::Dashboard => self.render_dashboard(frame, page_area),
+            Page::Fans => frame.render_widget(&self.fans_widget, page_area),
+            Page::Thermals => {
+                let [sensors_area, usage_area] =
+                    Layout::horizontal([Constraint::Fill(2), Constraint::Fill(1)]).areas(page_area);
+                frame.render_widget(&self.sensors_widget, sensors_area);
+                frame.render_widget(&self.usage_widget, usage_area);
+            }
+            Page::Power => {
+                let [left_col, usage_area] =
+                    Layout::horizontal([Constraint::Fill(2), Constraint::Fill(1)]).areas(page_area);
+                let [cpu_power_area, battery_area] =
+                    Layout::vertical([Constraint::Fill(1), Constraint::Fill(1)]).areas(left_col);
+                frame.render_widget(&self.cpu_power_widget, cpu_power_area);
+                frame.render_widget(&self.battery_widget, battery_area);
+                frame.render_widget(&self.usage_widget, usage_area);
+            }
+            Page::Processes => {
+                let [processes_area, cores_area] =
+                    Layout::vertical([Constraint::Fill(1), Constraint::Length(10)])
+                        .areas(page_area);
+                frame.render_widget(&self.processes_widget, processes_area);
+                frame.render_widget(&self.cores_widget, cores_area);
+            }
+            Page::Logs => frame.render_widget(&self.logs_widget, page_area),
+            Page::Settings => frame.render_widget(&self.settings_widget, page_area),
+        }
+    }
+
+    // the overview: fans and cores on the left, usage and logs on the right
+    fn render_dashboard(&self, frame: &mut Frame, area: Rect) {
+        let [left_col, right_col] =
+            Layout::horizontal([Constraint::Fill(2), Constraint::Fill(1)]).areas(area);
+
+        let [fanwidget_area, core_usage_area] =
+            Layout::vertical([Constraint::Fill(1), Constraint::Length(10)]).areas(left_col);
+        let [heat_area, logs_area] =
+            Layout::vertical([Constraint::Fill(3), Constraint::Fill(2)]).areas(right_col);
+
         frame.render_widget(&self.fans_widget, fanwidget_area);
         frame.render_widget(&self.cores_widget, core_usage_area);
 
@@ -120,9 +318,13 @@ impl App<'_> {
         frame.render_widget(&self.logs_widget, logs_area);
     }
 
-
-    fn push_log(&mut self) {
-        self.logs_widget.
+    fn set_page(&mut self, page: Page) {
+        if page == self.navbar.page {
+            return;
+        }
+        self.navbar.page = page;
+        // keys shouldn't keep going to a panel that's no longer on screen
+        self.set_focus(focus_order(page).first().copied());
     }
 
     fn exit(&mut self) {
@@ -130,21 +332,34 @@ impl App<'_> {
     }
 }
 
-impl Default for App<'_> {
-    fn default() -> Self {
-        Self {
+impl App {
+    pub fn new(backend: Backend, logs: LogHandle) -> io::Result<Self> {
+        let intervals = Intervals::default();
+        let history = HistoryHandle::new();
+        let curves = CurvesHandle::new();
+
+        let mut app = Self {
             exit: false,
-            counter: Counter {
-                title: "X",
-                count: 0,
-            },
+            sampler: Sampler::spawn(backend.clone(), intervals, history.clone(), curves.clone())?,
+            logs: logs.clone(),
+            sensor_errors: Vec::new(),
+            focus: None,
 
-            navbar: Navbar::new("Navbar"),
-            fans_widget: FansWidget::new("Fans"),
+            navbar: Navbar::new(backend.clone(), logs.clone()),
+            navbar_area: Rect::default(),
+            fans_widget: FansWidget::new("Fans", backend.clone(), logs.clone(), curves),
             cores_widget: CoresWidget::new("Cores"),
 
-            usage_widget: UsageWidget::new("Usage"),
-            logs_widget: Logs::new("Logs"),
-        }
+            usage_widget: UsageWidget::new("Usage", history),
+            logs_widget: Logs::new("Logs", logs.clone()),
+            processes_widget: ProcessesWidget::new("Processes"),
+            settings_widget: SettingsWidget::new("Settings", intervals),
+
+            sensors_widget: SensorsWidget::new("Sensors"),
+            cpu_power_widget: CpuPowerWidget::new("CPU power", backend.clone(), logs.clone()),
+            battery_widget: BatteryWidget::new("Battery", backend, logs),
+        };
+        app.set_focus(focus_order(app.navbar.page).first().copied());
+        Ok(app)
     }
 }
@@ -0,0 +1,88 @@
+use std::{env, path::PathBuf};
+
+use crate::{
+    hardware::{mock::READERS, root::ROOT_ENV},
+    logs::journald,
+};
+
+pub struct Args {
+    pub root: Option<PathBuf>,
+    pub mock: bool,
+    // fans of every hwmon chip, not just hp-wmi
+    pub all_fans: bool,
+    // readers the mock backend should fail, see hardware::mock::READERS
+    pub mock_fail: Vec<String>,
+    // None: the default under $XDG_STATE_HOME
+    pub log_file: Option<PathBuf>,
+    pub no_log_file: bool,
+    // journald socket to send logs to, if any
+    pub journald: Option<PathBuf>,
+}
+
+impl Args {
+    pub fn parse() -> Self {
+        let mut args = Args {
+            root: None,
+            mock: false,
+            all_fans: false,
+            mock_fail: Vec::new(),
+            log_file: None,
+            no_log_file: false,
+            journald: None,
+        };
+
+        let mut iter = env::args().skip(1);
+        while let Some(arg) = iter.next() {
+            match arg.as_str() {
+                "--root" => args.root = iter.next().map(PathBuf::from),
+                "--mock" => args.mock = true,
+                "--all-fans" => args.all_fans = true,
+                "--mock-fail" => {
+                    args.mock = true;
+                    args.mock_fail.extend(iter.next());
+                }
+                "--log-file" => args.log_file = iter.next().map(PathBuf::from),
+                "--no-log-file" => args.no_log_file = true,
+                "--journald" => args.journald = Some(PathBuf::from(journald::SOCKET)),
+                "--journald-socket" => args.journald = iter.next().map(PathBuf::from),
+                "-h" | "--help" => {
+                    print_help();
+                    std::process::exit(0);
+                }
+                other => {
+                    if let Some(path) = other.strip_prefix("--root=") {
+                        args.root = Some(PathBuf::from(path));
+                    } else {
+                        eprintln!("unknown argument: {}", other);
+                        print_help();
+                        std::process::exit(2);
+                    }
+                }
+            }
+        }
+
+        args
+    }
+}
+
+fn print_help() {
+    println!("usage: my-template [--root <path>] [--all-fans] [--mock] [--mock-fail <reader>]");
+    println!("                   [--log-file <path> | --no-log-file] [--journald]");
+    println!("                   [--journald-socket <path>]");
+    println!();
+    println!("  --root <path>   read /sys and /proc from <path> instead of /");
+    println!("                  (also settable with ${})", ROOT_ENV);
+    println!("  --all-fans      also show fans of other hwmon chips, not just hp-wmi");
+    println!("  --mock          replay canned values instead of touching hardware");
+    println!("  --mock-fail <reader>");
+    println!("                  make a mock reader fail, one of:");
+    println!("                  {}", READERS.join(", "));
+    println!("  --log-file <path>");
+    println!("                  append logs to <path> (default");
+    println!("                  $XDG_STATE_HOME/my-template/my-template.log)");
+    println!("  --no-log-file   keep logs in memory only");
+    println!("  --journald      also send logs to the systemd journal");
+    println!("  --journald-socket <path>");
+    println!("                  like --journald, through another socket");
+    println!("                  (default {})", journald::SOCKET);
+}
@@ -0,0 +1,457 @@
+use std::{
+    collections::HashMap,
+    fmt,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use tracing::{debug, debug_span, error};
+
+use crate::{
+    hardware::{Backend, FanMode},
+    sampler::Snapshot,
+};
+
+// rpm changes smaller than this aren't worth a write (or a log line)
+const MIN_CHANGE: u16 = 50;
+
+// ºC range points can be moved in, and the editor's x axis
+pub const TEMP_MIN: f64 = 20.;
+pub const TEMP_MAX: f64 = 100.;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CurveSource {
+    Cpu,
+    Gpu,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CurveUnit {
+    Rpm,
+    // of fan{N}_max
+    Percent,
+}
+
+impl fmt::Display for CurveSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            CurveSource::Cpu => "CPU",
+            CurveSource::Gpu => "GPU",
+        })
+    }
+}
+
+impl fmt::Display for CurveUnit {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            CurveUnit::Rpm => "rpm",
+            CurveUnit::Percent => "%",
+        })
+    }
+}
+
+// Piecewise linear temperature (ºC) -> fan speed curve.
+#[derive(Clone, Debug)]
+pub struct FanCurve {
+    // (temp, value), kept sorted by temp
+    pub points: Vec<(f64, f64)>,
+    pub unit: CurveUnit,
+    pub source: CurveSource,
+    // how far the temp has to drop below where we last ramped up before
+    // we consider ramping down
+    pub hysteresis: f64,
+    // and how long it has to stay there
+    pub ramp_down_delay: Duration,
+}
+
+impl Default for FanCurve {
+    fn default() -> Self {
+        Self {
+            points: vec![(40., 20.), (55., 35.), (65., 50.), (75., 70.), (85., 100.)],
+            unit: CurveUnit::Percent,
+            source: CurveSource::Cpu,
+            hysteresis: 3.,
+            ramp_down_delay: Duration::from_secs(5),
+        }
+    }
+}
+
+impl FanCurve {
+    // value at `temp`, flat before the first and after the last point
+    pub fn eval(&self, temp: f64) -> f64 {
+        let (Some(first), Some(last)) = (self.points.first(), self.points.last()) else {
+            return 0.;
+        };
+
+        if temp <= first.0 {
+            return first.1;
+        }
+        if temp >= last.0 {
+            return last.1;
+        }
+
+        for pair in self.points.windows(2) {
+            let ((t0, v0), (t1, v1)) = (pair[0], pair[1]);
+            if temp <= t1 {
+                if t1 == t0 {
+                    return v1;
+                }
+                return v0 + (v1 - v0) * (temp - t0) / (t1 - t0);
+            }
+        }
+
+        last.1
+    }
+
+    pub fn to_rpm(&self, value: f64, max: u16) -> u16 {
+        let rpm = match self.unit {
+            CurveUnit::Rpm => value,
+            CurveUnit::Percent => value / 100. * f64::from(max),
+        };
+
+        rpm.clamp(0., f64::from(max)).round() as u16
+    }
+
+    pub fn rpm(&self, temp: f64, max: u16) -> u16 {
+        self.to_rpm(self.eval(temp), max)
+    }
+
+    // highest value the y axis has to show
+    pub fn value_max(&self, max: u16) -> f64 {
+        match self.unit {
+            CurveUnit::Rpm => f64::from(max.max(1)),
+            CurveUnit::Percent => 100.,
+        }
+    }
+
+    pub fn set_unit(&mut self, unit: CurveUnit, max: u16) {
+        if unit == self.unit || max == 0 {
+            return;
+        }
+
+        let max = f64::from(max);
+        for (_, value) in &mut self.points {
+            *value = match unit {
+                CurveUnit::Rpm => (*value / 100. * max).round(),
+                CurveUnit::Percent => (*value / max * 100.).round(),
+            };
+        }
+        self.unit = unit;
+    }
+
+    // moves point `idx` keeping the points sorted and in range,
+    // returns the new index of the point
+    pub fn move_point(&mut self, idx: usize, temp: f64, value: f64, max: u16) -> usize {
+        let value_max = self.value_max(max);
+        let Some(point) = self.points.get_mut(idx) else {
+            return idx;
+        };
+
+        *point = (temp.clamp(TEMP_MIN, TEMP_MAX), value.clamp(0., value_max));
+        let moved = *point;
+
+        self.points.sort_by(|a, b| a.0.total_cmp(&b.0));
+        self.points.iter().position(|p| *p == moved).unwrap_or(idx)
+    }
+}
+
+// Drives one fan from its curve. Ramps up right away, ramps down only once
+// the temp is `hysteresis` below where we last ramped up and stays there
+// for `ramp_down_delay`.
+#[derive(Clone, Debug)]
+pub struct CurveController {
+    pub curve: FanCurve,
+    pub enabled: bool,
+
+    last_target: Option<u16>,
+    // temp at which last_target was picked
+    anchor_temp: f64,
+    cooler_since: Option<Instant>,
+}
+
+impl CurveController {
+    pub fn new(curve: FanCurve) -> Self {
+        Self {
+            curve,
+            enabled: false,
+            last_target: None,
+            anchor_temp: 0.,
+            cooler_since: None,
+        }
+    }
+
+    pub fn set_enabled(&mut self, enabled: bool) {
+        self.enabled = enabled;
+        self.last_target = None;
+        self.cooler_since = None;
+    }
+
+    // the rpm to write now, if any
+    pub fn tick(&mut self, temp: f64, max: u16, now: Instant) -> Option<u16> {
+        if !self.enabled || max == 0 {
+            return None;
+        }
+
+        let desired = self.curve.rpm(temp, max);
+
+        let Some(current) = self.last_target else {
+            return self.apply(desired, temp);
+        };
+
+        if desired >= current {
+            self.cooler_since = None;
+            if desired - current < MIN_CHANGE {
+                return None;
+            }
+            return self.apply(desired, temp);
+        }
+
+        if temp > self.anchor_temp - self.curve.hysteresis || current - desired < MIN_CHANGE {
+            self.cooler_since = None;
+            return None;
+        }
+
+        let since = *self.cooler_since.get_or_insert(now);
+        if now.duration_since(since) < self.curve.ramp_down_delay {
+            return None;
+        }
+
+        self.apply(desired, temp)
+    }
+
+    fn apply(&mut self, rpm: u16, temp: f64) -> Option<u16> {
+        self.last_target = Some(rpm);
+        self.anchor_temp = temp;
+        self.cooler_since = None;
+        Some(rpm)
+    }
+}
+
+// Every fan's controller by id. The fans widget edits them, the sampler
+// thread ticks them and writes the targets, so a busy UI never holds up a fan.
+// `curves` is only ever locked briefly, never across a sysfs write.
+#[derive(Clone, Default)]
+pub struct CurvesHandle {
+    curves: Arc<Mutex<HashMap<usize, CurveController>>>,
+    // held across every target write, by a curve or by hand, so a target set
+    // by hand can't be overtaken by the last write of the curve it turned off
+    writes: Arc<Mutex<()>>,
+}
+
+impl CurvesHandle {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // a copy of fan `id`'s controller, for drawing
+    pub fn get(&self, id: usize) -> CurveController {
+        match self.curves.lock().unwrap().get(&id) {
+            Some(controller) => controller.clone(),
+            None => CurveController::new(FanCurve::default()),
+        }
+    }
+
+    // turns fan `id`'s curve off and runs `write`, the target set by hand;
+    // true if the curve was on
+    pub fn take_over<T>(&self, id: usize, write: impl FnOnce() -> T) -> (bool, T) {
+        let _writes = self.writes.lock().unwrap();
+        let was_enabled = self.edit(id, |controller| {
+            let enabled = controller.enabled;
+            controller.set_enabled(false);
+            enabled
+        });
+        (was_enabled, write())
+    }
+
+    // fan `id`'s controller, on the default curve until someone edits it
+    pub fn edit<T>(&self, id: usize, f: impl FnOnce(&mut CurveController) -> T) -> T {
+        let mut curves = self.curves.lock().unwrap();
+        f(curves
+            .entry(id)
+            .or_insert_with(|| CurveController::new(FanCurve::default())))
+    }
+
+    // turns every curve off, true if any was on
+    pub fn disable_all(&self) -> bool {
+        let mut any = false;
+        for controller in self.curves.lock().unwrap().values_mut() {
+            any |= controller.enabled;
+            controller.set_enabled(false);
+        }
+        any
+    }
+
+    // writes whatever the enabled curves ask for
+    pub fn run(&self, backend: &Backend, snapshot: &Snapshot, now: Instant) {
+        // targets do nothing while the fans are pinned, or if we can't tell
+        if snapshot.fan_mode.value != Ok(FanMode::Auto) {
+            return;
+        }
+
+        let mut targets = Vec::new();
+        for fan in &snapshot.fans {
+            let mut curves = self.curves.lock().unwrap();
+            let Some(controller) = curves.get_mut(&fan.id) else {
+                continue;
+            };
+            let (Some(temp), Ok(max)) = (
+                snapshot.curve_temp(controller.curve.source),
+                fan.max.clone(),
+            ) else {
+                continue;
+            };
+            if let Some(rpm) = controller.tick(temp, max, now) {
+                targets.push((fan.id, rpm, temp));
+            }
+        }
+
+        let _writes = self.writes.lock().unwrap();
+        for (fan, rpm, temp) in targets {
+            // turned off since the tick, by hand or with auto mode
+            if !self
+                .curves
+                .lock()
+                .unwrap()
+                .get(&fan)
+                .is_some_and(|c| c.enabled)
+            {
+                continue;
+            }
+
+            let _span = debug_span!("curve", fan, temp).entered();
+            match backend.set_fan_target(fan, rpm) {
+                Ok(()) => debug!("fan {} curve: {} rpm at {:.0}ºC", fan, rpm, temp),
+                Err(err) => error!("fan {} curve: setting {} rpm failed: {}", fan, rpm, err),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MAX: u16 = 6000;
+
+    // percent curve, 3ºC hysteresis, 5s ramp down delay
+    fn controller() -> CurveController {
+        let mut controller = CurveController::new(FanCurve::default());
+        controller.set_enabled(true);
+        controller
+    }
+
+    #[test]
+    fn eval_interpolates_and_is_flat_outside() {
+        let curve = FanCurve::default();
+
+        assert_eq!(curve.eval(20.), 20.);
+        assert_eq!(curve.eval(40.), 20.);
+        assert_eq!(curve.eval(60.), 42.5);
+        assert_eq!(curve.eval(85.), 100.);
+        assert_eq!(curve.eval(105.), 100.);
+        assert_eq!(curve.rpm(60., MAX), 2550);
+    }
+
+    #[test]
+    fn move_point_keeps_points_sorted() {
+        let mut curve = FanCurve::default();
+
+        // the 55º point past the 65º one
+        let idx = curve.move_point(1, 70., 40., MAX);
+        assert_eq!(idx, 2);
+        assert_eq!(curve.points[2], (70., 40.));
+        assert!(curve.points.windows(2).all(|pair| pair[0].0 <= pair[1].0));
+
+        let idx = curve.move_point(idx, 70., 150., MAX);
+        assert_eq!(curve.points[idx].1, 100.);
+
+        // no further than the editor's axis goes
+        let idx = curve.move_point(idx, 130., 40., MAX);
+        assert_eq!(curve.points[idx].0, TEMP_MAX);
+        let idx = curve.move_point(idx, -5., 40., MAX);
+        assert_eq!(curve.points[idx].0, TEMP_MIN);
+    }
+
+    #[test]
+    fn disabled_does_nothing() {
+        let mut controller = CurveController::new(FanCurve::default());
+        assert_eq!(controller.tick(80., MAX, Instant::now()), None);
+    }
+
+    #[test]
+    fn ramps_up_right_away() {
+        let mut controller = controller();
+        let now = Instant::now();
+
+        assert_eq!(controller.tick(55., MAX, now), Some(2100));
+        assert_eq!(controller.tick(65., MAX, now), Some(3000));
+        // too small a change to write
+        assert_eq!(controller.tick(65.1, MAX, now), None);
+    }
+
+    #[test]
+    fn small_drops_stay_within_hysteresis() {
+        let mut controller = controller();
+        let now = Instant::now();
+
+        assert_eq!(controller.tick(65., MAX, now), Some(3000));
+        let later = now + Duration::from_secs(60);
+        assert_eq!(controller.tick(63., MAX, later), None);
+        assert_eq!(
+            controller.tick(63., MAX, later + Duration::from_secs(60)),
+            None
+        );
+    }
+
+    #[test]
+    fn ramps_down_after_the_delay() {
+        let mut controller = controller();
+        let now = Instant::now();
+
+        assert_eq!(controller.tick(65., MAX, now), Some(3000));
+        assert_eq!(controller.tick(55., MAX, now), None);
+        assert_eq!(
+            controller.tick(55., MAX, now + Duration::from_secs(4)),
+            None
+        );
+        assert_eq!(
+            controller.tick(55., MAX, now + Duration::from_secs(5)),
+            Some(2100)
+        );
+    }
+
+    #[test]
+    fn warming_up_resets_the_delay() {
+        let mut controller = controller();
+        let now = Instant::now();
+
+        assert_eq!(controller.tick(65., MAX, now), Some(3000));
+        assert_eq!(controller.tick(55., MAX, now), None);
+        // back within hysteresis before the delay is up
+        assert_eq!(
+            controller.tick(64., MAX, now + Duration::from_secs(3)),
+            None
+        );
+        assert_eq!(
+            controller.tick(55., MAX, now + Duration::from_secs(6)),
+            None
+        );
+        assert_eq!(
+            controller.tick(55., MAX, now + Duration::from_secs(11)),
+            Some(2100)
+        );
+    }
+
+    #[test]
+    fn reading_a_curve_creates_none() {
+        let curves = CurvesHandle::new();
+        assert!(!curves.get(1).enabled);
+        assert!(curves.curves.lock().unwrap().is_empty());
+
+        let (was_enabled, _) = curves.take_over(1, || ());
+        assert!(!was_enabled);
+        curves.edit(2, |controller| controller.set_enabled(true));
+        assert_eq!(curves.take_over(2, || 5), (true, 5));
+        assert!(!curves.get(2).enabled);
+    }
+}
@@ -0,0 +1,38 @@
+// Small helpers for reading and writing single-value sysfs attributes.
+
+use std::{
+    fs::{self, OpenOptions},
+    io::Write,
+    path::Path,
+    str::FromStr,
+};
+
+use tracing::debug;
+
+use crate::hardware::error::{HwError, HwResult};
+
+pub fn read_attr(path: &Path) -> HwResult<String> {
+    fs::read_to_string(path)
+        .map(|contents| contents.trim().to_string())
+        .map_err(|err| HwError::from_io(err, path))
+}
+
+pub fn read_value<T: FromStr>(path: &Path) -> HwResult<T> {
+    let contents = read_attr(path)?;
+
+    contents
+        .parse()
+        .map_err(|_| HwError::parse(path.display(), &contents))
+}
+
+pub fn write_attr(path: &Path, value: &str) -> HwResult<()> {
+    debug!(path = %path.display(), value, "write");
+
+    // truncate is a no-op on sysfs but keeps fake trees (see fixtures/) sane
+    OpenOptions::new()
+        .write(true)
+        .truncate(true)
+        .open(path)
+        .and_then(|mut file| file.write_all(value.as_bytes()))
+        .map_err(|err| HwError::from_io(err, path))
+}
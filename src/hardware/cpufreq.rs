@@ -0,0 +1,244 @@
+// Per-core frequencies from /sys/devices/system/cpu/cpu*/cpufreq, and the
+// knobs of each cpufreq policy (governor, EPP) plus the global boost switch.
+
+use std::path::{Path, PathBuf};
+
+use crate::hardware::{
+    attr::{read_attr, read_value, write_attr},
+    error::{HwError, HwResult},
+    root::SysRoot,
+};
+
+// all in GHz
+#[derive(Clone, Debug, PartialEq)]
+pub struct CoreFreq {
+    pub cpu: usize,
+    pub cur: f64,
+    pub min: f64,
+    pub max: f64,
+}
+
+// "/sys/devices/system/cpu/cpu12/cpufreq" -> 12
+fn cpu_number(dir: &Path) -> Option<usize> {
+    dir.parent()?
+        .file_name()?
+        .to_str()?
+        .strip_prefix("cpu")?
+        .parse()
+        .ok()
+}
+
+// cpufreq files are in kHz
+fn ghz(dir: &Path, name: &str) -> HwResult<f64> {
+    Ok(read_value::<f64>(&dir.join(name))? / 1_000_000.0)
+}
+
+// every online core with cpufreq, sorted by cpu number; offline cores have no
+// cpufreq dir (or an unreadable one) and are left out
+pub fn scan_freqs(root: &SysRoot) -> HwResult<Vec<CoreFreq>> {
+    let mut freqs: Vec<CoreFreq> = root
+        .sys_glob("devices/system/cpu/cpu[0-9]*/cpufreq")
+        .iter()
+        .filter_map(|dir| {
+            Some(CoreFreq {
+                cpu: cpu_number(dir)?,
+                cur: ghz(dir, "scaling_cur_freq").ok()?,
+                min: ghz(dir, "cpuinfo_min_freq").ok()?,
+                max: ghz(dir, "cpuinfo_max_freq").ok()?,
+            })
+        })
+        .collect();
+
+    if freqs.is_empty() {
+        return Err(HwError::missing("cpufreq"));
+    }
+
+    freqs.sort_by_key(|freq| freq.cpu);
+    Ok(freqs)
+}
+
+pub fn average(freqs: &[CoreFreq]) -> f64 {
+    freqs.iter().map(|freq| freq.cur).sum::<f64>() / freqs.len().max(1) as f64
+}
+
+pub fn fastest(freqs: &[CoreFreq]) -> f64 {
+    freqs.iter().map(|freq| freq.cur).fold(0.0, f64::max)
+}
+
+// highest cpuinfo_max_freq, the top of the chart
+pub fn ceiling(freqs: &[CoreFreq]) -> f64 {
+    freqs.iter().map(|freq| freq.max).fold(0.0, f64::max)
+}
+
+// one /sys/devices/system/cpu/cpufreq/policyN
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct CpuPolicy {
+    // "policy0"
+    pub name: String,
+    // affected_cpus, e.g. "0" or "0 1"
+    pub cpus: String,
+    // scaling_driver, e.g. "amd-pstate-epp", "intel_pstate"
+    pub driver: String,
+    pub governor: String,
+    pub governors: Vec<String>,
+    // only with amd-pstate / intel_pstate in active mode
+    pub epp: Option<String>,
+    pub epp_choices: Vec<String>,
+}
+
+fn words(path: &Path) -> Vec<String> {
+    read_attr(path)
+        .map(|value| value.split_whitespace().map(str::to_string).collect())
+        .unwrap_or_default()
+}
+
+fn policy_dir(root: &SysRoot, policy: &str) -> PathBuf {
+    root.sys(&format!("devices/system/cpu/cpufreq/{}", policy))
+}
+
+pub fn scan_policies(root: &SysRoot) -> HwResult<Vec<CpuPolicy>> {
+    let mut policies: Vec<(usize, CpuPolicy)> = root
+        .sys_glob("devices/system/cpu/cpufreq/policy[0-9]*")
+        .iter()
+        .filter_map(|dir| {
+            let name = dir.file_name()?.to_str()?.to_string();
+            let number = name.strip_prefix("policy")?.parse().ok()?;
+
+            let policy = CpuPolicy {
+                cpus: read_attr(&dir.join("affected_cpus")).unwrap_or_default(),
+                driver: read_attr(&dir.join("scaling_driver")).unwrap_or_default(),
+                governor: read_attr(&dir.join("scaling_governor")).ok()?,
+                governors: words(&dir.join("scaling_available_governors")),
+                epp: read_attr(&dir.join("energy_performance_preference")).ok(),
+                epp_choices: words(&dir.join("energy_performance_available_preferences")),
+                name,
+            };
+            Some((number, policy))
+        })
+        .collect();
+
+    if policies.is_empty() {
+        return Err(HwError::missing("cpufreq policies"));
+    }
+
+    policies.sort_by_key(|(number, _)| *number);
+    Ok(policies.into_iter().map(|(_, policy)| policy).collect())
+}
+
+pub fn set_governor(root: &SysRoot, policy: &str, governor: &str) -> HwResult<()> {
+    write_attr(&policy_dir(root, policy).join("scaling_governor"), governor)
+}
+
+pub fn set_epp(root: &SysRoot, policy: &str, epp: &str) -> HwResult<()> {
+    write_attr(
+        &policy_dir(root, policy).join("energy_performance_preference"),
+        epp,
+    )
+}
+
+// cpufreq/boost (acpi-cpufreq, amd-pstate), or intel_pstate's inverted no_turbo
+pub fn boost(root: &SysRoot) -> HwResult<bool> {
+    let boost = root.sys("devices/system/cpu/cpufreq/boost");
+    if boost.exists() {
+        return Ok(read_value::<u8>(&boost)? == 1);
+    }
+
+    let no_turbo = root.sys("devices/system/cpu/intel_pstate/no_turbo");
+    if no_turbo.exists() {
+        return Ok(read_value::<u8>(&no_turbo)? == 0);
+    }
+
+    Err(HwError::missing("cpu boost control"))
+}
+
+pub fn set_boost(root: &SysRoot, enabled: bool) -> HwResult<()> {
+    let boost = root.sys("devices/system/cpu/cpufreq/boost");
+    if boost.exists() {
+        return write_attr(&boost, if enabled { "1" } else { "0" });
+    }
+
+    let no_turbo = root.sys("devices/system/cpu/intel_pstate/no_turbo");
+    if no_turbo.exists() {
+        return write_attr(&no_turbo, if enabled { "0" } else { "1" });
+    }
+
+    Err(HwError::missing("cpu boost control"))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{env, fs, process};
+
+    use super::*;
+
+    fn fixture() -> SysRoot {
+        SysRoot::new(concat!(env!("CARGO_MANIFEST_DIR"), "/fixtures/hp-wmi"))
+    }
+
+    #[test]
+    fn freqs_follow_the_cpu_links() {
+        let freqs = scan_freqs(&fixture()).unwrap();
+
+        let cpus: Vec<usize> = freqs.iter().map(|freq| freq.cpu).collect();
+        assert_eq!(cpus, [0, 1, 2, 3]);
+        assert_eq!(
+            freqs[1],
+            CoreFreq {
+                cpu: 1,
+                cur: 2.845,
+                min: 0.4,
+                max: 4.7,
+            }
+        );
+        assert_eq!(fastest(&freqs), 4.12);
+        assert_eq!(ceiling(&freqs), 4.7);
+    }
+
+    #[test]
+    fn policies_read_their_choices() {
+        let policies = scan_policies(&fixture()).unwrap();
+
+        assert_eq!(policies.len(), 4);
+        assert_eq!(
+            policies[2],
+            CpuPolicy {
+                name: "policy2".to_string(),
+                cpus: "2".to_string(),
+                driver: "amd-pstate-epp".to_string(),
+                governor: "powersave".to_string(),
+                governors: vec!["performance".to_string(), "powersave".to_string()],
+                epp: Some("balance_performance".to_string()),
+                epp_choices: [
+                    "default",
+                    "performance",
+                    "balance_performance",
+                    "balance_power",
+                    "power",
+                ]
+                .map(str::to_string)
+                .to_vec(),
+            }
+        );
+        assert_eq!(boost(&fixture()), Ok(true));
+    }
+
+    #[test]
+    fn no_turbo_is_boost_inverted() {
+        let root = env::temp_dir().join(format!("my-template-cpufreq-{}", process::id()));
+        let pstate = root.join("sys/devices/system/cpu/intel_pstate");
+        fs::create_dir_all(&pstate).unwrap();
+        fs::write(pstate.join("no_turbo"), "0\n").unwrap();
+        let sys = SysRoot::new(&root);
+
+        let on = boost(&sys);
+        set_boost(&sys, false).unwrap();
+        let written = fs::read_to_string(pstate.join("no_turbo")).unwrap();
+        let off = boost(&sys);
+        fs::remove_dir_all(&root).unwrap();
+
+        assert_eq!(on, Ok(true));
+        assert_eq!(written.trim(), "1");
+        assert_eq!(off, Ok(false));
+        assert!(boost(&sys).is_err());
+    }
+}
@@ -0,0 +1,70 @@
+use std::{
+    fmt, io,
+    path::{Path, PathBuf},
+};
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum HwError {
+    // the file / device / sensor isn't there at all
+    MissingDevice(String),
+    PermissionDenied(PathBuf),
+    Parse { what: String, value: String },
+    CommandMissing(String),
+    CommandFailed { command: String, stderr: String },
+    Io { path: PathBuf, message: String },
+}
+
+pub type HwResult<T> = Result<T, HwError>;
+
+impl HwError {
+    pub fn missing(what: impl fmt::Display) -> Self {
+        HwError::MissingDevice(what.to_string())
+    }
+
+    pub fn parse(what: impl fmt::Display, value: &str) -> Self {
+        HwError::Parse {
+            what: what.to_string(),
+            value: value.to_string(),
+        }
+    }
+
+    pub fn from_io(err: io::Error, path: &Path) -> Self {
+        match err.kind() {
+            io::ErrorKind::NotFound => HwError::MissingDevice(path.display().to_string()),
+            io::ErrorKind::PermissionDenied => HwError::PermissionDenied(path.to_path_buf()),
+            _ => HwError::Io {
+                path: path.to_path_buf(),
+                message: err.to_string(),
+            },
+        }
+    }
+
+    pub fn from_spawn(err: io::Error, command: &str) -> Self {
+        match err.kind() {
+            io::ErrorKind::NotFound => HwError::CommandMissing(command.to_string()),
+            _ => HwError::CommandFailed {
+                command: command.to_string(),
+                stderr: err.to_string(),
+            },
+        }
+    }
+}
+
+impl fmt::Display for HwError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            HwError::MissingDevice(what) => write!(f, "missing device: {}", what),
+            HwError::PermissionDenied(path) => {
+                write!(f, "permission denied: {}", path.display())
+            }
+            HwError::Parse { what, value } => write!(f, "couldn't parse {}: {:?}", what, value),
+            HwError::CommandMissing(command) => write!(f, "command not found: {}", command),
+            HwError::CommandFailed { command, stderr } => {
+                write!(f, "{} failed: {}", command, stderr.trim())
+            }
+            HwError::Io { path, message } => write!(f, "{}: {}", path.display(), message),
+        }
+    }
+}
+
+impl std::error::Error for HwError {}
@@ -0,0 +1,340 @@
+// GPUs under /sys/class/drm, each read by whichever provider fits its driver:
+// plain sysfs for amdgpu / i915 / xe (and nouveau's hwmon), nvidia-smi (see
+// nvidia.rs) for the proprietary nvidia driver.
+
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use tracing::{debug, debug_span, info};
+
+use crate::hardware::{
+    attr::{read_attr, read_value},
+    error::{HwError, HwResult},
+    nvidia::{NvidiaSmiGpu, NvidiaStream},
+    root::SysRoot,
+};
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct GpuCard {
+    // "card1"
+    pub name: String,
+    // PCI slot, e.g. "0000:01:00.0"
+    pub pci: String,
+    // bound kernel driver, e.g. "amdgpu", "i915", "xe", "nvidia"
+    pub driver: String,
+}
+
+// Whatever the provider could find out, None for what it can't.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct GpuReading {
+    // 0 to 100
+    pub busy: Option<f32>,
+    // ºC
+    pub temp: Option<f64>,
+    // MiB
+    pub vram_used: Option<u64>,
+    pub vram_total: Option<u64>,
+    // shader / graphics and memory clock, MHz
+    pub sclk: Option<u32>,
+    pub mclk: Option<u32>,
+    // W
+    pub power: Option<f64>,
+    // 0 to 100, None on passively cooled cards and iGPUs
+    pub fan: Option<f32>,
+    // "P0" (fastest) to "P12", nvidia only
+    pub pstate: Option<String>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum GpuState {
+    Active(GpuReading),
+    // runtime suspended, left alone so it stays that way
+    Suspended,
+}
+
+impl Default for GpuState {
+    fn default() -> Self {
+        GpuState::Active(GpuReading::default())
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct GpuStatus {
+    pub card: GpuCard,
+    pub state: HwResult<GpuState>,
+}
+
+pub trait GpuProvider: Send + Sync {
+    fn card(&self) -> &GpuCard;
+    fn read(&self) -> HwResult<GpuReading>;
+    // called instead of read while the card is runtime suspended
+    fn suspended(&self) {}
+}
+
+// amdgpu, i915, xe and anything else with a drm device dir
+pub struct SysfsGpu {
+    card: GpuCard,
+    // /sys/class/drm/cardN
+    card_dir: PathBuf,
+    // /sys/class/drm/cardN/device
+    device: PathBuf,
+}
+
+fn first_hwmon(device: &Path) -> Option<PathBuf> {
+    let mut dirs: Vec<PathBuf> = fs::read_dir(device.join("hwmon"))
+        .ok()?
+        .filter_map(Result::ok)
+        .map(|entry| entry.path())
+        .collect();
+    dirs.sort();
+    dirs.into_iter().next()
+}
+
+// the active level of amdgpu's pp_dpm_sclk / pp_dpm_mclk, "1: 1800Mhz *"
+fn active_dpm_level(text: &str) -> Option<u32> {
+    let line = text.lines().find(|line| line.trim_end().ends_with('*'))?;
+    let (_, clock) = line.split_once(':')?;
+    clock
+        .trim()
+        .trim_end_matches('*')
+        .trim()
+        .trim_end_matches("Mhz")
+        .trim_end_matches("MHz")
+        .parse()
+        .ok()
+}
+
+impl SysfsGpu {
+    fn sclk(&self) -> Option<u32> {
+        match self.card.driver.as_str() {
+            "amdgpu" => active_dpm_level(&read_attr(&self.device.join("pp_dpm_sclk")).ok()?),
+            "i915" => read_value(&self.card_dir.join("gt_act_freq_mhz")).ok(),
+            "xe" => read_value(&self.device.join("tile0/gt0/freq0/act_freq")).ok(),
+            _ => None,
+        }
+    }
+
+    fn mclk(&self) -> Option<u32> {
+        match self.card.driver.as_str() {
+            "amdgpu" => active_dpm_level(&read_attr(&self.device.join("pp_dpm_mclk")).ok()?),
+            _ => None,
+        }
+    }
+}
+
+impl GpuProvider for SysfsGpu {
+    fn card(&self) -> &GpuCard {
+        &self.card
+    }
+
+    fn read(&self) -> HwResult<GpuReading> {
+        if !self.device.exists() {
+            return Err(HwError::missing(self.device.display()));
+        }
+
+        let hwmon = first_hwmon(&self.device);
+        let hwmon_value =
+            |name: &str| -> Option<f64> { read_value::<f64>(&hwmon.as_ref()?.join(name)).ok() };
+        let mib = |name: &str| -> Option<u64> {
+            read_value::<u64>(&self.device.join(name))
+                .ok()
+                .map(|bytes| bytes / 1024 / 1024)
+        };
+
+        Ok(GpuReading {
+            busy: read_value(&self.device.join("gpu_busy_percent")).ok(),
+            temp: hwmon_value("temp1_input").map(|milli| milli / 1000.),
+            vram_used: mib("mem_info_vram_used"),
+            vram_total: mib("mem_info_vram_total"),
+            sclk: self.sclk(),
+            mclk: self.mclk(),
+            // µW; amdgpu has power1_average on older parts, power1_input on newer
+            power: hwmon_value("power1_average")
+                .or_else(|| hwmon_value("power1_input"))
+                .map(|micro| micro / 1_000_000.),
+            // pwm1 is 0-255
+            fan: hwmon_value("pwm1").map(|pwm| (pwm / 255. * 100.) as f32),
+            pstate: None,
+        })
+    }
+}
+
+// PCI runtime PM state. Anything that talks to the driver (nvidia-smi, most
+// amdgpu attributes) resumes a sleeping dGPU and keeps it up for seconds, so
+// this is checked first and only this file is read while it sleeps.
+fn runtime_suspended(root: &SysRoot, pci: &str) -> bool {
+    let path = root.sys(&format!("bus/pci/devices/{}/power/runtime_status", pci));
+    matches!(
+        read_attr(&path).as_deref(),
+        Ok("suspended") | Ok("suspending")
+    )
+}
+
+pub fn status(root: &SysRoot, provider: &dyn GpuProvider) -> GpuStatus {
+    let card = provider.card().clone();
+
+    let state = match runtime_suspended(root, &card.pci) {
+        true => {
+            provider.suspended();
+            Ok(GpuState::Suspended)
+        }
+        false => provider.read().map(GpuState::Active),
+    };
+
+    GpuStatus { card, state }
+}
+
+// cardN dirs only, not the cardN-eDP-1 style connectors next to them
+fn is_card(name: &str) -> bool {
+    name.strip_prefix("card")
+        .is_some_and(|n| !n.is_empty() && n.chars().all(|c| c.is_ascii_digit()))
+}
+
+fn link_name(path: &Path) -> Option<String> {
+    Some(fs::read_link(path).ok()?.file_name()?.to_str()?.to_string())
+}
+
+// one provider per card, picked by the driver bound to it
+pub fn discover(root: &SysRoot) -> Vec<Box<dyn GpuProvider>> {
+    let mut providers: Vec<Box<dyn GpuProvider>> = Vec::new();
+    // one nvidia-smi for all nvidia cards
+    let mut nvidia: Option<Arc<NvidiaStream>> = None;
+
+    for card_dir in root.sys_glob("class/drm/card*") {
+        let Some(name) = card_dir.file_name().and_then(|n| n.to_str()) else {
+            continue;
+        };
+        if !is_card(name) {
+            continue;
+        }
+
+        let device = card_dir.join("device");
+        let Some(driver) = link_name(&device.join("driver")) else {
+            continue;
+        };
+        let card = GpuCard {
+            name: name.to_string(),
+            pci: link_name(&device).unwrap_or_default(),
+            driver,
+        };
+
+        let _span = debug_span!("probe", card = %card.name, driver = %card.driver).entered();
+        match card.driver.as_str() {
+            "nvidia" => {
+                info!(pci = %card.pci, "reading through nvidia-smi");
+                let stream = nvidia.get_or_insert_with(Default::default).clone();
+                providers.push(Box::new(NvidiaSmiGpu::new(card, stream)));
+            }
+            "amdgpu" | "i915" | "xe" | "nouveau" | "radeon" => {
+                info!(pci = %card.pci, "reading through sysfs");
+                providers.push(Box::new(SysfsGpu {
+                    card,
+                    card_dir: card_dir.clone(),
+                    device,
+                }));
+            }
+            // simpledrm, virtio, ... nothing worth reading
+            _ => debug!("skipped, nothing to read"),
+        }
+    }
+
+    providers
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{env, process};
+
+    use super::*;
+
+    fn fixture() -> SysRoot {
+        SysRoot::new(concat!(env!("CARGO_MANIFEST_DIR"), "/fixtures/hp-wmi"))
+    }
+
+    #[test]
+    fn discovers_both_cards() {
+        let cards: Vec<GpuCard> = discover(&fixture())
+            .iter()
+            .map(|provider| provider.card().clone())
+            .collect();
+
+        assert_eq!(
+            cards,
+            [
+                GpuCard {
+                    name: "card0".to_string(),
+                    pci: "0000:01:00.0".to_string(),
+                    driver: "nvidia".to_string(),
+                },
+                GpuCard {
+                    name: "card1".to_string(),
+                    pci: "0000:05:00.0".to_string(),
+                    driver: "amdgpu".to_string(),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn active_cards_are_read() {
+        let root = fixture();
+        let providers = discover(&root);
+
+        let status = status(&root, providers[1].as_ref());
+        assert_eq!(
+            status.state,
+            Ok(GpuState::Active(GpuReading {
+                busy: Some(23.),
+                temp: Some(49.),
+                vram_used: Some(1440),
+                vram_total: Some(4096),
+                sclk: Some(1800),
+                mclk: None,
+                power: Some(8.125),
+                fan: None,
+                pstate: None,
+            }))
+        );
+    }
+
+    #[test]
+    fn suspended_cards_are_left_asleep() {
+        let root = fixture();
+        let providers = discover(&root);
+
+        // read() would start nvidia-smi, which isn't there to answer
+        let status = status(&root, providers[0].as_ref());
+        assert_eq!(status.card.driver, "nvidia");
+        assert_eq!(status.state, Ok(GpuState::Suspended));
+    }
+
+    #[test]
+    fn missing_busy_percent_is_none() {
+        let dir = env::temp_dir().join(format!("my-template-gpu-{}", process::id()));
+        let device = dir.join("device");
+        fs::create_dir_all(device.join("hwmon/hwmon2")).unwrap();
+        fs::write(device.join("hwmon/hwmon2/temp1_input"), "61000\n").unwrap();
+        fs::write(dir.join("gt_act_freq_mhz"), "1300\n").unwrap();
+
+        let gpu = SysfsGpu {
+            card: GpuCard {
+                name: "card0".to_string(),
+                pci: "0000:00:02.0".to_string(),
+                driver: "i915".to_string(),
+            },
+            card_dir: dir.clone(),
+            device,
+        };
+        let reading = gpu.read();
+        fs::remove_dir_all(&dir).unwrap();
+
+        let reading = reading.unwrap();
+        assert_eq!(reading.busy, None);
+        assert_eq!(reading.temp, Some(61.));
+        assert_eq!(reading.sclk, Some(1300));
+        assert_eq!(reading.vram_total, None);
+    }
+}
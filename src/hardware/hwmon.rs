@@ -0,0 +1,183 @@
+// Temperature sensors of every hwmon chip under /sys/class/hwmon.
+
+use std::{fs, path::Path};
+
+use crate::hardware::{
+    attr::{read_attr, read_value},
+    error::HwResult,
+    root::SysRoot,
+};
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct TempSensor {
+    // "hwmon5/temp1", only stable for one boot
+    pub id: String,
+    // hwmon `name`, e.g. "k10temp", "nvme", "acpitz"
+    pub chip: String,
+    // temp{N}_label, or "temp{N}" when there is none
+    pub label: String,
+    // all in ºC
+    pub input: f64,
+    pub max: Option<f64>,
+    pub crit: Option<f64>,
+}
+
+impl TempSensor {
+    pub fn name(&self) -> String {
+        format!("{} {}", self.chip, self.label)
+    }
+}
+
+// N of every temp{N}_input in a hwmon dir, sorted
+fn temp_indexes(dir: &Path) -> Vec<usize> {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return Vec::new();
+    };
+
+    let mut indexes: Vec<usize> = entries
+        .filter_map(Result::ok)
+        .filter_map(|entry| {
+            let name = entry.file_name().to_string_lossy().to_string();
+            name.strip_prefix("temp")?
+                .strip_suffix("_input")?
+                .parse()
+                .ok()
+        })
+        .collect();
+
+    indexes.sort();
+    indexes
+}
+
+fn millidegrees(path: &Path) -> HwResult<f64> {
+    read_value::<i64>(path).map(|value| value as f64 / 1000.)
+}
+
+pub fn scan_temps(root: &SysRoot) -> Vec<TempSensor> {
+    let mut sensors = Vec::new();
+
+    for dir in root.sys_glob("class/hwmon/hwmon*") {
+        let hwmon = dir
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_default();
+        let chip = read_attr(&dir.join("name")).unwrap_or_else(|_| hwmon.clone());
+
+        for index in temp_indexes(&dir) {
+            // sleeping devices (dGPUs, some nvme) fail with EIO/ENODATA, skip them
+            let Ok(input) = millidegrees(&dir.join(format!("temp{}_input", index))) else {
+                continue;
+            };
+
+            let label = read_attr(&dir.join(format!("temp{}_label", index)))
+                .ok()
+                .filter(|label| !label.is_empty())
+                .unwrap_or_else(|| format!("temp{}", index));
+
+            sensors.push(TempSensor {
+                id: format!("{}/temp{}", hwmon, index),
+                chip: chip.clone(),
+                label,
+                input,
+                max: millidegrees(&dir.join(format!("temp{}_max", index))).ok(),
+                crit: millidegrees(&dir.join(format!("temp{}_crit", index))).ok(),
+            });
+        }
+    }
+
+    sensors
+}
+
+// Best guess at the sensor that means "CPU" when the user hasn't picked one.
+pub fn default_cpu_sensor(sensors: &[TempSensor]) -> Option<&TempSensor> {
+    let preferred: [(&str, Option<&str>); 7] = [
+        ("k10temp", Some("Tctl")),
+        ("k10temp", Some("Tdie")),
+        ("k10temp", None),
+        ("zenpower", None),
+        ("coretemp", Some("Package id 0")),
+        ("coretemp", None),
+        ("cpu_thermal", None),
+    ];
+
+    preferred
+        .iter()
+        .find_map(|(chip, label)| {
+            sensors
+                .iter()
+                .find(|s| s.chip == *chip && label.is_none_or(|label| s.label == label))
+        })
+        .or_else(|| sensors.iter().find(|s| s.chip == "acpitz"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sensor(chip: &str, label: &str) -> TempSensor {
+        TempSensor {
+            id: format!("{}/{}", chip, label),
+            chip: chip.to_string(),
+            label: label.to_string(),
+            input: 50.,
+            max: None,
+            crit: None,
+        }
+    }
+
+    #[test]
+    fn scans_every_chip_in_the_root() {
+        let root = SysRoot::new(concat!(env!("CARGO_MANIFEST_DIR"), "/fixtures/hp-wmi"));
+        let sensors = scan_temps(&root);
+
+        let names: Vec<String> = sensors.iter().map(TempSensor::name).collect();
+        // the hp fan chip has no temperatures
+        assert_eq!(
+            names,
+            [
+                "acpitz temp1",
+                "nvme Composite",
+                "k10temp Tctl",
+                "k10temp Tccd1",
+                "amdgpu edge"
+            ]
+        );
+
+        assert_eq!(
+            sensors[1],
+            TempSensor {
+                id: "hwmon1/temp1".to_string(),
+                chip: "nvme".to_string(),
+                label: "Composite".to_string(),
+                input: 38.85,
+                max: Some(81.85),
+                crit: Some(84.85),
+            }
+        );
+        assert_eq!((sensors[0].max, sensors[0].crit), (None, Some(105.)));
+        assert_eq!(sensors[3].id, "hwmon5/temp3");
+        assert_eq!(sensors[3].input, 49.125);
+
+        assert_eq!(default_cpu_sensor(&sensors).unwrap().name(), "k10temp Tctl");
+    }
+
+    #[test]
+    fn cpu_chips_win_over_acpitz() {
+        let intel = [
+            sensor("acpitz", "temp1"),
+            sensor("coretemp", "Core 0"),
+            sensor("coretemp", "Package id 0"),
+        ];
+        assert_eq!(
+            default_cpu_sensor(&intel).unwrap().name(),
+            "coretemp Package id 0"
+        );
+
+        let amd = [sensor("acpitz", "temp1"), sensor("k10temp", "Tccd1")];
+        assert_eq!(default_cpu_sensor(&amd).unwrap().name(), "k10temp Tccd1");
+
+        let neither = [sensor("nvme", "Composite"), sensor("acpitz", "temp1")];
+        assert_eq!(default_cpu_sensor(&neither).unwrap().name(), "acpitz temp1");
+        assert_eq!(default_cpu_sensor(&[sensor("nvme", "Composite")]), None);
+    }
+}
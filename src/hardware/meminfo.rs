@@ -0,0 +1,138 @@
+// Memory stats straight from /proc/meminfo, no `free` involved.
+
+use std::collections::HashMap;
+
+use crate::hardware::error::{HwError, HwResult};
+
+// everything in KiB, except the hugepage counts
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct MemInfo {
+    pub total: u64,
+    pub free: u64,
+    pub available: u64,
+    pub buffers: u64,
+    pub cached: u64,
+    pub swap_total: u64,
+    pub swap_free: u64,
+    pub dirty: u64,
+    pub hugepages_total: u64,
+    pub hugepages_free: u64,
+    pub hugepage_size: u64,
+}
+
+impl MemInfo {
+    // what `free` calls used: everything the kernel can't give back
+    pub fn used(&self) -> u64 {
+        self.total.saturating_sub(self.available)
+    }
+
+    // page cache + buffers, capped so used + cache never goes over total
+    pub fn cache(&self) -> u64 {
+        (self.buffers + self.cached).min(self.available)
+    }
+
+    pub fn swap_used(&self) -> u64 {
+        self.swap_total.saturating_sub(self.swap_free)
+    }
+
+    // lines look like "MemTotal:       16131012 kB" or "HugePages_Total:       0"
+    pub fn parse(text: &str) -> HwResult<Self> {
+        let fields: HashMap<&str, u64> = text
+            .lines()
+            .filter_map(|line| {
+                let (key, rest) = line.split_once(':')?;
+                let value = rest.split_whitespace().next()?.parse().ok()?;
+                Some((key.trim(), value))
+            })
+            .collect();
+
+        let required = |key: &str| -> HwResult<u64> {
+            fields
+                .get(key)
+                .copied()
+                .ok_or_else(|| HwError::missing(format!("{} in /proc/meminfo", key)))
+        };
+        let optional = |key: &str| fields.get(key).copied().unwrap_or(0);
+
+        let total = required("MemTotal")?;
+        let free = required("MemFree")?;
+        // MemAvailable only exists since 3.14, estimate it like old `free` did
+        let buffers = optional("Buffers");
+        let cached = optional("Cached");
+        let available = fields
+            .get("MemAvailable")
+            .copied()
+            .unwrap_or(free + buffers + cached);
+
+        Ok(Self {
+            total,
+            free,
+            available,
+            buffers,
+            cached,
+            swap_total: optional("SwapTotal"),
+            swap_free: optional("SwapFree"),
+            dirty: optional("Dirty"),
+            hugepages_total: optional("HugePages_Total"),
+            hugepages_free: optional("HugePages_Free"),
+            hugepage_size: optional("Hugepagesize"),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MEMINFO: &str = include_str!("../../fixtures/hp-wmi/proc/meminfo");
+
+    // a pre 3.14 kernel
+    const OLD_MEMINFO: &str = "MemTotal:        8000000 kB
+MemFree:         1000000 kB
+Buffers:          200000 kB
+Cached:          3000000 kB
+SwapCached:            0 kB
+SwapTotal:       2000000 kB
+SwapFree:        2000000 kB
+";
+
+    #[test]
+    fn parses_every_field() {
+        let mem = MemInfo::parse(MEMINFO).unwrap();
+
+        assert_eq!(
+            mem,
+            MemInfo {
+                total: 16131012,
+                free: 5012344,
+                available: 9876540,
+                buffers: 412380,
+                cached: 4321096,
+                swap_total: 8388604,
+                swap_free: 7864316,
+                dirty: 18432,
+                hugepages_total: 0,
+                hugepages_free: 0,
+                hugepage_size: 2048,
+            }
+        );
+        assert_eq!(mem.used(), 16131012 - 9876540);
+        assert_eq!(mem.cache(), 412380 + 4321096);
+        assert_eq!(mem.swap_used(), 524288);
+    }
+
+    #[test]
+    fn available_falls_back_to_free_buffers_and_cached() {
+        let mem = MemInfo::parse(OLD_MEMINFO).unwrap();
+
+        assert_eq!(mem.available, 4200000);
+        assert_eq!(mem.used(), 3800000);
+        assert_eq!(mem.dirty, 0);
+    }
+
+    #[test]
+    fn total_and_free_are_required() {
+        assert!(MemInfo::parse("MemFree: 1000 kB\n").is_err());
+        assert!(MemInfo::parse("MemTotal: 1000 kB\n").is_err());
+    }
+}
@@ -0,0 +1,792 @@
+use std::{collections::HashMap, sync::Mutex, time::Instant};
+
+use crate::hardware::{
+    FanInfo, FanMode, HardwareBackend,
+    cpufreq::{CoreFreq, CpuPolicy},
+    error::{HwError, HwResult},
+    gpu::{GpuCard, GpuReading, GpuState, GpuStatus},
+    hwmon::TempSensor,
+    meminfo::MemInfo,
+    power_supply::{Battery, ChargeThreshold, PowerSupplies},
+    process::ProcessInfo,
+    rapl::RaplZone,
+};
+
+// names accepted by `MockBackend::failing` / `--mock-fail`
+pub const READERS: &[&str] = &[
+    "fans",
+    "fan_speed",
+    "fan_max",
+    "fan_target",
+    "set_fan_target",
+    "fan_mode",
+    "set_fan_mode",
+    "platform_profile",
+    "set_platform_profile",
+    "temp_sensors",
+    "gpus",
+    "memory",
+    "cpu_freq",
+    "cpu_usage",
+    "cpu_policies",
+    "set_governor",
+    "set_epp",
+    "cpu_boost",
+    "set_cpu_boost",
+    "rapl_zones",
+    "power_supplies",
+    "set_charge_threshold",
+    "processes",
+];
+
+// A list of values handed out one per read, starting over at the end.
+// An empty script reads as T::default().
+#[derive(Clone, Default)]
+pub struct Script<T> {
+    values: Vec<T>,
+    pos: usize,
+}
+
+impl<T: Clone + Default> Script<T> {
+    pub fn new(values: Vec<T>) -> Self {
+        Self { values, pos: 0 }
+    }
+
+    pub fn next(&mut self) -> T {
+        if self.values.is_empty() {
+            return T::default();
+        }
+
+        let value = self.values[self.pos % self.values.len()].clone();
+        self.pos += 1;
+        value
+    }
+}
+
+#[derive(Default)]
+struct MockState {
+    fan_speeds: HashMap<usize, Script<u16>>,
+    fan_max: HashMap<usize, u16>,
+    fan_targets: HashMap<usize, u16>,
+    fan_mode: FanMode,
+    platform_profile: String,
+    platform_profile_choices: Vec<String>,
+
+    cpu_temps: Script<u64>,
+    gpus: Vec<(GpuCard, Script<GpuState>)>,
+    memory: Script<MemInfo>,
+    cpu_freqs: Script<Vec<CoreFreq>>,
+    cpu_usage: Script<Vec<f32>>,
+    cpu_policies: Vec<CpuPolicy>,
+    cpu_boost: Option<bool>,
+    // counters, watts per zone replayed per read, and when they were last read
+    rapl: Vec<RaplZone>,
+    rapl_watts: Script<Vec<f64>>,
+    rapl_read: Option<Instant>,
+    // capacity per read, the rest of the battery is fixed
+    battery: Option<(Battery, Script<u8>)>,
+    ac_online: bool,
+    // (pid, name, MiB) and the cpu % of each per read
+    processes: Vec<(u32, String, u64)>,
+    process_cpu: Script<Vec<f32>>,
+
+    failures: HashMap<&'static str, HwError>,
+}
+
+impl MockState {
+    fn check(&self, reader: &str) -> HwResult<()> {
+        match self.failures.get(reader) {
+            Some(err) => Err(err.clone()),
+            None => Ok(()),
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct MockBackend {
+    state: Mutex<MockState>,
+}
+
+impl MockBackend {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // Somewhat believable values, used by `--mock`.
+    pub fn demo() -> Self {
+        Self::new()
+            .fan(1, 5800, vec![2600, 2650, 2700, 2750, 2700, 2650])
+            .fan(2, 6100, vec![2900, 2950, 3000, 3050, 3000, 2950])
+            .platform_profiles(&["quiet", "balanced", "performance"], "balanced")
+            .cpu_temps(vec![48, 52, 58, 66, 71, 64, 55, 50])
+            .gpu(
+                "card1",
+                "0000:05:00.0",
+                "amdgpu",
+                vec![(4, 44, 600), (38, 51, 1800), (71, 58, 2200), (22, 49, 1200)],
+            )
+            .gpu(
+                "card0",
+                "0000:01:00.0",
+                "nvidia",
+                vec![
+                    (0, 0, 0),
+                    (0, 0, 0),
+                    (12, 45, 1100),
+                    (64, 52, 1650),
+                    (30, 47, 900),
+                ],
+            )
+            .memory(vec![
+                meminfo(15_800, 6_200, 3_100, 120),
+                meminfo(15_800, 6_900, 3_300, 120),
+                meminfo(15_800, 7_400, 3_600, 180),
+            ])
+            .cpu_freqs(
+                0.4,
+                4.7,
+                vec![
+                    vec![1.4, 0.4, 2.1, 0.4, 3.0, 0.4, 1.1, 0.6],
+                    vec![2.9, 1.8, 1.6, 3.9, 2.7, 0.9, 0.5, 4.2],
+                    vec![4.1, 4.0, 3.6, 4.5, 3.4, 4.2, 3.8, 4.4],
+                    vec![3.3, 2.2, 2.9, 3.1, 1.7, 2.4, 3.0, 2.6],
+                ],
+            )
+            .cpu_usage(vec![
+                vec![12.0, 4.0, 30.0, 8.0, 55.0, 3.0, 20.0, 9.0],
+                vec![40.0, 22.0, 18.0, 60.0, 35.0, 10.0, 5.0, 70.0],
+                vec![90.0, 85.0, 70.0, 95.0, 60.0, 88.0, 75.0, 92.0],
+            ])
+            .processes(
+                &[
+                    (1, "systemd", 14),
+                    (812, "Xwayland", 96),
+                    (1204, "gnome-shell", 412),
+                    (2210, "firefox", 1830),
+                    (2391, "Isolated Web Co", 640),
+                    (3077, "cargo", 210),
+                    (3102, "rustc", 1250),
+                    (3350, "alacritty", 88),
+                ],
+                vec![
+                    vec![0.0, 1.2, 6.5, 18.0, 9.1, 0.4, 96.0, 0.8],
+                    vec![0.1, 2.8, 4.9, 22.4, 31.5, 1.9, 184.0, 1.1],
+                    vec![0.0, 0.9, 3.3, 11.7, 6.0, 0.0, 0.0, 0.5],
+                ],
+            )
+            .cpu_policies(
+                8,
+                &["performance", "powersave"],
+                &[
+                    "default",
+                    "performance",
+                    "balance_performance",
+                    "balance_power",
+                    "power",
+                ],
+            )
+            .cpu_boost(true)
+            .rapl(
+                &["package-0", "core", "uncore"],
+                vec![
+                    vec![8.5, 4.1, 2.0],
+                    vec![14.2, 9.8, 2.4],
+                    vec![31.0, 24.5, 3.9],
+                    vec![22.7, 16.3, 3.1],
+                ],
+            )
+            .battery(vec![82, 81, 81, 80, 79, 79, 78])
+    }
+
+    pub fn fan(self, fan: usize, max: u16, speeds: Vec<u16>) -> Self {
+        {
+            let mut state = self.state.lock().unwrap();
+            state.fan_max.insert(fan, max);
+            state.fan_speeds.insert(fan, Script::new(speeds));
+        }
+        self
+    }
+
+    pub fn platform_profiles(self, choices: &[&str], current: &str) -> Self {
+        {
+            let mut state = self.state.lock().unwrap();
+            state.platform_profile_choices = choices.iter().map(|c| c.to_string()).collect();
+            state.platform_profile = current.to_string();
+        }
+        self
+    }
+
+    pub fn cpu_temps(self, temps: Vec<u64>) -> Self {
+        self.state.lock().unwrap().cpu_temps = Script::new(temps);
+        self
+    }
+
+    // (busy %, ºC, MHz) per read, memory, power and (on nvidia) fan and
+    // P-state follow from busy. (0, 0, 0) is a runtime suspended card
+    pub fn gpu(self, name: &str, pci: &str, driver: &str, samples: Vec<(u8, u8, u32)>) -> Self {
+        let card = GpuCard {
+            name: name.to_string(),
+            pci: pci.to_string(),
+            driver: driver.to_string(),
+        };
+        let nvidia = driver == "nvidia";
+        let readings = samples
+            .into_iter()
+            .map(|(busy, temp, sclk)| match (busy, temp, sclk) {
+                (0, 0, 0) => GpuState::Suspended,
+                _ => GpuState::Active(GpuReading {
+                    busy: Some(f32::from(busy)),
+                    temp: Some(f64::from(temp)),
+                    vram_used: Some(300 + u64::from(busy) * 30),
+                    vram_total: Some(4096),
+                    sclk: Some(sclk),
+                    mclk: Some(if busy < 20 { 405 } else { 7001 }),
+                    power: Some(3. + f64::from(busy) * 0.6),
+                    fan: nvidia.then(|| 30. + f32::from(busy) / 2.),
+                    pstate: nvidia.then(|| if busy < 20 { "P8" } else { "P2" }.to_string()),
+                }),
+            })
+            .collect();
+
+        self.state
+            .lock()
+            .unwrap()
+            .gpus
+            .push((card, Script::new(readings)));
+        self
+    }
+
+    pub fn memory(self, memory: Vec<MemInfo>) -> Self {
+        self.state.lock().unwrap().memory = Script::new(memory);
+        self
+    }
+
+    // amd-pstate-epp style: one policy per core, all starting on powersave
+    pub fn cpu_policies(self, count: usize, governors: &[&str], epp_choices: &[&str]) -> Self {
+        let strings = |values: &[&str]| values.iter().map(|v| v.to_string()).collect();
+
+        self.state.lock().unwrap().cpu_policies = (0..count)
+            .map(|cpu| CpuPolicy {
+                name: format!("policy{}", cpu),
+                cpus: cpu.to_string(),
+                driver: "amd-pstate-epp".to_string(),
+                governor: "powersave".to_string(),
+                governors: strings(governors),
+                epp: Some("balance_performance".to_string()),
+                epp_choices: strings(epp_choices),
+            })
+            .collect();
+        self
+    }
+
+    // a worn 52.5Wh battery discharging at ~11W, capacities replayed per read
+    pub fn battery(self, capacities: Vec<u8>) -> Self {
+        let battery = Battery {
+            name: "BAT0".to_string(),
+            model: "Primary".to_string(),
+            status: "Discharging".to_string(),
+            energy_full: 47.3,
+            energy_full_design: 52.5,
+            power_now: 11.2,
+            cycle_count: Some(214),
+            charge_end: Some(80),
+            ..Battery::default()
+        };
+        self.state.lock().unwrap().battery = Some((battery, Script::new(capacities)));
+        self
+    }
+
+    // zones as on a single socket machine, intel-rapl:0 with its subzones
+    pub fn rapl(self, names: &[&str], watts: Vec<Vec<f64>>) -> Self {
+        {
+            let mut state = self.state.lock().unwrap();
+            state.rapl = names
+                .iter()
+                .enumerate()
+                .map(|(idx, name)| RaplZone {
+                    id: match idx {
+                        0 => "intel-rapl:0".to_string(),
+                        idx => format!("intel-rapl:0:{}", idx - 1),
+                    },
+                    name: name.to_string(),
+                    energy_uj: 0,
+                    max_energy_range_uj: 262_143_328_850,
+                })
+                .collect();
+            state.rapl_watts = Script::new(watts);
+        }
+        self
+    }
+
+    pub fn cpu_boost(self, enabled: bool) -> Self {
+        self.state.lock().unwrap().cpu_boost = Some(enabled);
+        self
+    }
+
+    // one vec of per-core GHz per sample, all cores sharing the same bounds
+    pub fn cpu_freqs(self, min: f64, max: f64, samples: Vec<Vec<f64>>) -> Self {
+        let samples = samples
+            .into_iter()
+            .map(|cores| {
+                cores
+                    .into_iter()
+                    .enumerate()
+                    .map(|(cpu, cur)| CoreFreq { cpu, cur, min, max })
+                    .collect()
+            })
+            .collect();
+        self.state.lock().unwrap().cpu_freqs = Script::new(samples);
+        self
+    }
+
+    pub fn cpu_usage(self, usage: Vec<Vec<f32>>) -> Self {
+        self.state.lock().unwrap().cpu_usage = Script::new(usage);
+        self
+    }
+
+    // (pid, name, MiB) and per read the cpu % of each, in the same order
+    pub fn processes(self, processes: &[(u32, &str, u64)], cpu: Vec<Vec<f32>>) -> Self {
+        {
+            let mut state = self.state.lock().unwrap();
+            state.processes = processes
+                .iter()
+                .map(|&(pid, name, mib)| (pid, name.to_string(), mib))
+                .collect();
+            state.process_cpu = Script::new(cpu);
+        }
+        self
+    }
+
+    // makes every call to `reader` (one of READERS) return `err`
+    pub fn failing(self, reader: &'static str, err: HwError) -> Self {
+        self.state.lock().unwrap().failures.insert(reader, err);
+        self
+    }
+}
+
+// total / used / cache in MiB, 8GiB of swap with `swap_used` MiB in use
+fn meminfo(total: u64, used: u64, cache: u64, swap_used: u64) -> MemInfo {
+    MemInfo {
+        total: total * 1024,
+        free: (total - used - cache) * 1024,
+        available: (total - used) * 1024,
+        cached: cache * 1024,
+        swap_total: 8192 * 1024,
+        swap_free: (8192 - swap_used) * 1024,
+        dirty: 24 * 1024,
+        hugepage_size: 2048,
+        ..MemInfo::default()
+    }
+}
+
+impl HardwareBackend for MockBackend {
+    fn fans(&self) -> HwResult<Vec<FanInfo>> {
+        let state = self.state.lock().unwrap();
+        state.check("fans")?;
+
+        let mut ids: Vec<usize> = state.fan_max.keys().copied().collect();
+        ids.sort();
+
+        Ok(ids
+            .into_iter()
+            .map(|id| FanInfo {
+                id,
+                label: format!("fan {}", id),
+                chip: "mock".to_string(),
+            })
+            .collect())
+    }
+
+    fn fan_speed(&self, fan: usize) -> HwResult<u16> {
+        let mut state = self.state.lock().unwrap();
+        state.check("fan_speed")?;
+
+        state
+            .fan_speeds
+            .get_mut(&fan)
+            .map(Script::next)
+            .ok_or_else(|| HwError::missing(format!("mock fan {}", fan)))
+    }
+
+    fn fan_max(&self, fan: usize) -> HwResult<u16> {
+        let state = self.state.lock().unwrap();
+        state.check("fan_max")?;
+
+        state
+            .fan_max
+            .get(&fan)
+            .copied()
+            .ok_or_else(|| HwError::missing(format!("mock fan {}", fan)))
+    }
+
+    fn fan_target(&self, fan: usize) -> HwResult<u16> {
+        let state = self.state.lock().unwrap();
+        state.check("fan_target")?;
+
+        if !state.fan_max.contains_key(&fan) {
+            return Err(HwError::missing(format!("mock fan {}", fan)));
+        }
+
+        Ok(state.fan_targets.get(&fan).copied().unwrap_or(0))
+    }
+
+    fn set_fan_target(&self, fan: usize, rpm: u16) -> HwResult<()> {
+        let mut state = self.state.lock().unwrap();
+        state.check("set_fan_target")?;
+
+        if !state.fan_max.contains_key(&fan) {
+            return Err(HwError::missing(format!("mock fan {}", fan)));
+        }
+
+        state.fan_targets.insert(fan, rpm);
+        Ok(())
+    }
+
+    fn fan_mode(&self) -> HwResult<FanMode> {
+        let state = self.state.lock().unwrap();
+        state.check("fan_mode")?;
+        Ok(state.fan_mode)
+    }
+
+    fn set_fan_mode(&self, mode: FanMode) -> HwResult<()> {
+        let mut state = self.state.lock().unwrap();
+        state.check("set_fan_mode")?;
+        state.fan_mode = mode;
+        Ok(())
+    }
+
+    fn platform_profile(&self) -> HwResult<String> {
+        let state = self.state.lock().unwrap();
+        state.check("platform_profile")?;
+
+        if state.platform_profile_choices.is_empty() {
+            return Err(HwError::missing("mock platform_profile"));
+        }
+        Ok(state.platform_profile.clone())
+    }
+
+    fn platform_profile_choices(&self) -> HwResult<Vec<String>> {
+        let state = self.state.lock().unwrap();
+        state.check("platform_profile")?;
+
+        if state.platform_profile_choices.is_empty() {
+            return Err(HwError::missing("mock platform_profile_choices"));
+        }
+        Ok(state.platform_profile_choices.clone())
+    }
+
+    fn set_platform_profile(&self, profile: &str) -> HwResult<()> {
+        let mut state = self.state.lock().unwrap();
+        state.check("set_platform_profile")?;
+
+        if !state.platform_profile_choices.iter().any(|c| c == profile) {
+            return Err(HwError::parse("platform_profile", profile));
+        }
+        state.platform_profile = profile.to_string();
+        Ok(())
+    }
+
+    fn temp_sensors(&self) -> HwResult<Vec<TempSensor>> {
+        let mut state = self.state.lock().unwrap();
+        state.check("temp_sensors")?;
+
+        let sensor =
+            |id: &str, chip: &str, label: &str, input: f64, crit: Option<f64>| TempSensor {
+                id: id.to_string(),
+                chip: chip.to_string(),
+                label: label.to_string(),
+                input,
+                max: None,
+                crit,
+            };
+
+        let cpu = state.cpu_temps.next() as f64;
+        Ok(vec![
+            sensor("hwmon0/temp1", "acpitz", "temp1", cpu - 8., Some(105.)),
+            sensor("hwmon1/temp1", "nvme", "Composite", 38.9, Some(84.9)),
+            sensor("hwmon5/temp1", "k10temp", "Tctl", cpu, None),
+            sensor("hwmon5/temp3", "k10temp", "Tccd1", cpu - 3., None),
+        ])
+    }
+
+    fn gpus(&self) -> HwResult<Vec<GpuStatus>> {
+        let mut state = self.state.lock().unwrap();
+        state.check("gpus")?;
+
+        if state.gpus.is_empty() {
+            return Err(HwError::missing("mock gpu"));
+        }
+
+        Ok(state
+            .gpus
+            .iter_mut()
+            .map(|(card, readings)| GpuStatus {
+                card: card.clone(),
+                state: Ok(readings.next()),
+            })
+            .collect())
+    }
+
+    fn memory(&self) -> HwResult<MemInfo> {
+        let mut state = self.state.lock().unwrap();
+        state.check("memory")?;
+        Ok(state.memory.next())
+    }
+
+    fn cpu_freqs(&self) -> HwResult<Vec<CoreFreq>> {
+        let mut state = self.state.lock().unwrap();
+        state.check("cpu_freq")?;
+        Ok(state.cpu_freqs.next())
+    }
+
+    fn cpu_usage(&self) -> HwResult<Vec<f32>> {
+        let mut state = self.state.lock().unwrap();
+        state.check("cpu_usage")?;
+        Ok(state.cpu_usage.next())
+    }
+
+    fn processes(&self) -> HwResult<Vec<ProcessInfo>> {
+        let mut state = self.state.lock().unwrap();
+        state.check("processes")?;
+
+        let cpu = state.process_cpu.next();
+        Ok(state
+            .processes
+            .iter()
+            .enumerate()
+            .map(|(idx, (pid, name, mib))| ProcessInfo {
+                pid: *pid,
+                name: name.clone(),
+                cpu: cpu.get(idx).copied().unwrap_or(0.),
+                memory: mib * 1024 * 1024,
+            })
+            .collect())
+    }
+
+    fn cpu_policies(&self) -> HwResult<Vec<CpuPolicy>> {
+        let state = self.state.lock().unwrap();
+        state.check("cpu_policies")?;
+
+        if state.cpu_policies.is_empty() {
+            return Err(HwError::missing("mock cpufreq policies"));
+        }
+        Ok(state.cpu_policies.clone())
+    }
+
+    fn set_governor(&self, policy: &str, governor: &str) -> HwResult<()> {
+        let mut state = self.state.lock().unwrap();
+        state.check("set_governor")?;
+
+        let policy = state
+            .cpu_policies
+            .iter_mut()
+            .find(|p| p.name == policy)
+            .ok_or_else(|| HwError::missing(format!("mock {}", policy)))?;
+        if !policy.governors.iter().any(|g| g == governor) {
+            return Err(HwError::parse("scaling_governor", governor));
+        }
+        policy.governor = governor.to_string();
+        Ok(())
+    }
+
+    fn set_epp(&self, policy: &str, epp: &str) -> HwResult<()> {
+        let mut state = self.state.lock().unwrap();
+        state.check("set_epp")?;
+
+        let policy = state
+            .cpu_policies
+            .iter_mut()
+            .find(|p| p.name == policy)
+            .ok_or_else(|| HwError::missing(format!("mock {}", policy)))?;
+        if !policy.epp_choices.iter().any(|e| e == epp) {
+            return Err(HwError::parse("energy_performance_preference", epp));
+        }
+        policy.epp = Some(epp.to_string());
+        Ok(())
+    }
+
+    fn cpu_boost(&self) -> HwResult<bool> {
+        let state = self.state.lock().unwrap();
+        state.check("cpu_boost")?;
+        state
+            .cpu_boost
+            .ok_or_else(|| HwError::missing("mock cpu boost"))
+    }
+
+    fn set_cpu_boost(&self, enabled: bool) -> HwResult<()> {
+        let mut state = self.state.lock().unwrap();
+        state.check("set_cpu_boost")?;
+
+        if state.cpu_boost.is_none() {
+            return Err(HwError::missing("mock cpu boost"));
+        }
+        state.cpu_boost = Some(enabled);
+        Ok(())
+    }
+
+    fn power_supplies(&self) -> HwResult<PowerSupplies> {
+        let mut state = self.state.lock().unwrap();
+        state.check("power_supplies")?;
+
+        let ac_online = state.ac_online;
+        let Some((battery, capacities)) = &mut state.battery else {
+            return Err(HwError::missing("mock battery"));
+        };
+
+        let capacity = capacities.next();
+        let mut battery = battery.clone();
+        battery.capacity = capacity;
+        battery.energy_now = battery.energy_full * f64::from(capacity) / 100.;
+        if ac_online {
+            battery.status = "Charging".to_string();
+        }
+
+        Ok(PowerSupplies {
+            batteries: vec![battery],
+            ac_online: Some(ac_online),
+            ..PowerSupplies::default()
+        })
+    }
+
+    fn set_charge_threshold(
+        &self,
+        battery: &str,
+        which: ChargeThreshold,
+        percent: u8,
+    ) -> HwResult<()> {
+        let mut state = self.state.lock().unwrap();
+        state.check("set_charge_threshold")?;
+
+        let Some((mock, _)) = state.battery.as_mut().filter(|(b, _)| b.name == battery) else {
+            return Err(HwError::missing(format!("mock {}", battery)));
+        };
+        match which {
+            ChargeThreshold::Start => {
+                return Err(HwError::missing(format!(
+                    "{} charge {} threshold",
+                    battery, which
+                )));
+            }
+            ChargeThreshold::End => mock.charge_end = Some(percent),
+        }
+        Ok(())
+    }
+
+    fn rapl_zones(&self) -> HwResult<Vec<RaplZone>> {
+        let mut state = self.state.lock().unwrap();
+        state.check("rapl_zones")?;
+
+        if state.rapl.is_empty() {
+            return Err(HwError::missing("mock powercap intel-rapl"));
+        }
+
+        // count up as if the scripted watts had been drawn since the last read
+        let now = Instant::now();
+        let elapsed = state
+            .rapl_read
+            .map(|read| now.duration_since(read).as_secs_f64())
+            .unwrap_or(0.);
+        state.rapl_read = Some(now);
+
+        let watts = state.rapl_watts.next();
+        for (zone, watts) in state.rapl.iter_mut().zip(watts) {
+            let uj = (watts * elapsed * 1_000_000.) as u64;
+            zone.energy_uj = (zone.energy_uj + uj) % zone.max_energy_range_uj;
+        }
+
+        Ok(state.rapl.clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn script_replays_in_order_and_starts_over() {
+        let mut script = Script::new(vec![1, 2, 3]);
+        let read: Vec<u8> = (0..7).map(|_| script.next()).collect();
+        assert_eq!(read, [1, 2, 3, 1, 2, 3, 1]);
+
+        let mut empty: Script<u8> = Script::default();
+        assert_eq!(empty.next(), 0);
+    }
+
+    #[test]
+    fn fans_replay_their_scripts() {
+        let mock = MockBackend::new()
+            .fan(2, 6000, vec![3000, 3100])
+            .fan(1, 5800, vec![2600]);
+
+        let ids: Vec<usize> = mock.fans().unwrap().iter().map(|fan| fan.id).collect();
+        assert_eq!(ids, [1, 2]);
+
+        assert_eq!(mock.fan_speed(2), Ok(3000));
+        assert_eq!(mock.fan_speed(2), Ok(3100));
+        assert_eq!(mock.fan_speed(2), Ok(3000));
+        assert_eq!(mock.fan_speed(1), Ok(2600));
+        assert_eq!(mock.fan_max(1), Ok(5800));
+        assert!(mock.fan_speed(3).is_err());
+    }
+
+    #[test]
+    fn writes_are_read_back() {
+        let mock = MockBackend::new()
+            .fan(1, 5800, vec![2600])
+            .platform_profiles(&["quiet", "balanced"], "balanced");
+
+        assert_eq!(mock.fan_target(1), Ok(0));
+        mock.set_fan_target(1, 3000).unwrap();
+        assert_eq!(mock.fan_target(1), Ok(3000));
+        assert!(mock.set_fan_target(7, 3000).is_err());
+
+        mock.set_fan_mode(FanMode::Max).unwrap();
+        assert_eq!(mock.fan_mode(), Ok(FanMode::Max));
+
+        mock.set_platform_profile("quiet").unwrap();
+        assert_eq!(mock.platform_profile().as_deref(), Ok("quiet"));
+        assert!(mock.set_platform_profile("turbo").is_err());
+        assert_eq!(mock.platform_profile().as_deref(), Ok("quiet"));
+    }
+
+    #[test]
+    fn gpu_samples_replay_as_states() {
+        let mock = MockBackend::new().gpu(
+            "card0",
+            "0000:01:00.0",
+            "nvidia",
+            vec![(0, 0, 0), (50, 60, 1500)],
+        );
+
+        let first = mock.gpus().unwrap();
+        assert_eq!(first[0].state, Ok(GpuState::Suspended));
+
+        let second = mock.gpus().unwrap();
+        let Ok(GpuState::Active(reading)) = &second[0].state else {
+            panic!("expected an active card, got {:?}", second[0].state);
+        };
+        assert_eq!(reading.temp, Some(60.));
+        assert_eq!(reading.pstate.as_deref(), Some("P2"));
+    }
+
+    #[test]
+    fn failing_readers_return_their_error() {
+        let err = HwError::missing("mock fan_speed");
+        let mock = MockBackend::new()
+            .fan(1, 5800, vec![2600])
+            .failing("fan_speed", err.clone());
+
+        assert_eq!(mock.fan_speed(1), Err(err));
+        // the others keep working
+        assert_eq!(mock.fan_max(1), Ok(5800));
+    }
+
+    #[test]
+    fn every_reader_can_fail() {
+        for reader in READERS {
+            let err = HwError::missing(reader);
+            let mock = MockBackend::demo().failing(reader, err.clone());
+            mock.state.lock().unwrap().check(reader).unwrap_err();
+        }
+    }
+}
@@ -0,0 +1,139 @@
+use std::{fmt, sync::Arc};
+
+use crate::hardware::{
+    cpufreq::{CoreFreq, CpuPolicy},
+    error::{HwError, HwResult},
+    gpu::GpuStatus,
+    hwmon::TempSensor,
+    meminfo::MemInfo,
+    power_supply::{ChargeThreshold, PowerSupplies},
+    process::ProcessInfo,
+    rapl::RaplZone,
+};
+
+pub mod attr;
+pub mod cpufreq;
+pub mod error;
+pub mod gpu;
+pub mod hwmon;
+pub mod meminfo;
+pub mod mock;
+pub mod nvidia;
+pub mod power_supply;
+pub mod process;
+pub mod procstat;
+pub mod rapl;
+pub mod root;
+pub mod sysfs;
+
+// What hp-wmi's pwm1_enable is set to. The driver only takes 0 and 2 and
+// answers anything else with EINVAL (hp_wmi_hwmon_write in
+// drivers/platform/x86/hp/hp-wmi.c), so there is no manual mode: fan{N}_target
+// is honoured under BIOS control and ignored while the fans are pinned at max.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum FanMode {
+    // 0, fans pinned at full speed
+    Max,
+    // 2, the BIOS decides, fan{N}_target included
+    #[default]
+    Auto,
+}
+
+impl FanMode {
+    pub fn from_pwm_enable(value: &str) -> HwResult<Self> {
+        match value.trim() {
+            "0" => Ok(FanMode::Max),
+            "2" => Ok(FanMode::Auto),
+            other => Err(HwError::parse("pwm1_enable", other)),
+        }
+    }
+
+    pub fn pwm_enable(self) -> &'static str {
+        match self {
+            FanMode::Max => "0",
+            FanMode::Auto => "2",
+        }
+    }
+}
+
+impl fmt::Display for FanMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            FanMode::Max => "max",
+            FanMode::Auto => "auto (BIOS)",
+        })
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct FanInfo {
+    // what every fan_* call takes, stable for the lifetime of the backend
+    pub id: usize,
+    // fan{N}_label if the chip has one
+    pub label: String,
+    // hwmon `name` of the chip it belongs to, e.g. "hp"
+    pub chip: String,
+}
+
+// Everything the widgets need from the machine. The real implementation reads
+// sysfs (and shells out where it has to), the mock replays scripted values so
+// the widgets can be driven without any hardware.
+pub trait HardwareBackend: Send + Sync {
+    // FANS
+    fn fans(&self) -> HwResult<Vec<FanInfo>>;
+    fn fan_speed(&self, fan: usize) -> HwResult<u16>;
+    fn fan_max(&self, fan: usize) -> HwResult<u16>;
+    // last value written to fan{N}_target
+    fn fan_target(&self, fan: usize) -> HwResult<u16>;
+    fn set_fan_target(&self, fan: usize, rpm: u16) -> HwResult<()>;
+    // pwm1_enable, shared by all fans
+    fn fan_mode(&self) -> HwResult<FanMode>;
+    fn set_fan_mode(&self, mode: FanMode) -> HwResult<()>;
+
+    // ACPI PLATFORM PROFILE, e.g. "quiet", "balanced", "performance"
+    fn platform_profile(&self) -> HwResult<String>;
+    fn platform_profile_choices(&self) -> HwResult<Vec<String>>;
+    fn set_platform_profile(&self, profile: &str) -> HwResult<()>;
+
+    // TEMPERATURES, in ºC
+    // every hwmon temperature the machine has
+    fn temp_sensors(&self) -> HwResult<Vec<TempSensor>>;
+
+    // GPU
+    // every card with a known driver, each read by its own provider
+    fn gpus(&self) -> HwResult<Vec<GpuStatus>>;
+
+    // MEMORY
+    fn memory(&self) -> HwResult<MemInfo>;
+
+    // CPU
+    // current frequency and bounds of every online core
+    fn cpu_freqs(&self) -> HwResult<Vec<CoreFreq>>;
+    // cpufreq policies, governor and energy performance preference per policy
+    fn cpu_policies(&self) -> HwResult<Vec<CpuPolicy>>;
+    fn set_governor(&self, policy: &str, governor: &str) -> HwResult<()>;
+    fn set_epp(&self, policy: &str, epp: &str) -> HwResult<()>;
+    // raw RAPL energy counters, see rapl::power_between for watts
+    fn rapl_zones(&self) -> HwResult<Vec<RaplZone>>;
+    // cpu boost / turbo, for all cores at once
+    fn cpu_boost(&self) -> HwResult<bool>;
+    fn set_cpu_boost(&self, enabled: bool) -> HwResult<()>;
+
+    // BATTERY / AC
+    fn power_supplies(&self) -> HwResult<PowerSupplies>;
+    // percent, only where the firmware has charge_control_*_threshold
+    fn set_charge_threshold(
+        &self,
+        battery: &str,
+        which: ChargeThreshold,
+        percent: u8,
+    ) -> HwResult<()>;
+    // per core, 0.0 to 100.0
+    fn cpu_usage(&self) -> HwResult<Vec<f32>>;
+
+    // PROCESSES
+    // every process, threads left out, in no particular order
+    fn processes(&self) -> HwResult<Vec<ProcessInfo>>;
+}
+
+pub type Backend = Arc<dyn HardwareBackend>;
@@ -0,0 +1,431 @@
+// NVIDIA cards on the proprietary driver, read through one long-lived
+// `nvidia-smi --query-gpu=... --loop-ms=N` child shared by every card. Spawning
+// nvidia-smi per read costs ~100ms of driver init each time; the loop mode
+// pays that once and then prints a CSV line per card every period.
+
+use std::{
+    collections::HashMap,
+    io::{BufRead, BufReader},
+    process::{Child, Command, Stdio},
+    sync::{Arc, Condvar, Mutex},
+    thread,
+    time::{Duration, Instant},
+};
+
+use tracing::{info, warn};
+
+use crate::hardware::{
+    error::{HwError, HwResult},
+    gpu::{GpuCard, GpuProvider, GpuReading},
+};
+
+// how often nvidia-smi prints, the sampler reads the latest line
+const PERIOD_MS: u64 = 1000;
+// before respawning a child that died or never started
+const RESTART_DELAY: Duration = Duration::from_secs(5);
+// every card idle this long pauses the child for REST, see NvidiaStream
+const IDLE_STOP: Duration = Duration::from_secs(30);
+const REST: Duration = Duration::from_secs(30);
+
+const QUERY: &str = "pci.bus_id,temperature.gpu,utilization.gpu,memory.used,memory.total,\
+power.draw,clocks.sm,clocks.mem,fan.speed,pstate";
+const FIELDS: usize = 10;
+
+// nvidia-smi prints an 8 digit domain ("00000000:01:00.0"), sysfs a 4 digit
+// one ("0000:01:00.0")
+pub fn pci_key(bus_id: &str) -> String {
+    let bus_id = bus_id.trim().to_lowercase();
+    match bus_id.split_once(':') {
+        Some((domain, rest)) => match u32::from_str_radix(domain, 16) {
+            Ok(domain) => format!("{:04x}:{}", domain, rest),
+            Err(_) => bus_id,
+        },
+        None => bus_id,
+    }
+}
+
+// "[N/A]", "N/A" or "[Not Supported]" for things the card doesn't report
+fn field<T: std::str::FromStr>(value: &str) -> Option<T> {
+    value.trim().parse().ok()
+}
+
+// one `--format=csv,noheader,nounits` line of QUERY, keyed by pci_key
+pub fn parse_line(line: &str) -> HwResult<(String, GpuReading)> {
+    let fields: Vec<&str> = line.split(',').map(str::trim).collect();
+    if fields.len() < FIELDS {
+        return Err(HwError::parse("nvidia-smi output", line));
+    }
+
+    let pstate = fields[9];
+    let reading = GpuReading {
+        temp: field(fields[1]),
+        busy: field(fields[2]),
+        vram_used: field(fields[3]),
+        vram_total: field(fields[4]),
+        power: field(fields[5]),
+        sclk: field(fields[6]),
+        mclk: field(fields[7]),
+        fan: field(fields[8]),
+        pstate: pstate.starts_with('P').then(|| pstate.to_string()),
+    };
+
+    Ok((pci_key(fields[0]), reading))
+}
+
+// Every reading in captured output, in order. Header lines (plain
+// `--format=csv`) and blank lines are skipped, so later lines for the same
+// card simply win.
+pub fn parse_csv(text: &str) -> Vec<HwResult<(String, GpuReading)>> {
+    text.lines()
+        .filter(|line| !line.trim().is_empty() && !line.starts_with("pci.bus_id"))
+        .map(parse_line)
+        .collect()
+}
+
+// Why the child was killed rather than dying on its own.
+enum Stop {
+    // every card went to sleep, nothing left to read
+    Asleep,
+    // every card sat idle for IDLE_STOP, see NvidiaStream
+    Idle,
+}
+
+#[derive(Default)]
+struct StreamState {
+    // the supervisor thread is running
+    started: bool,
+    // the running nvidia-smi, taken out by whoever stops or reaps it
+    child: Option<Child>,
+    stopped: Option<Stop>,
+    // every card read or reported asleep so far, by pci_key, true while it's
+    // runtime suspended
+    asleep: HashMap<String, bool>,
+    // latest reading of each card, by pci_key
+    readings: HashMap<String, GpuReading>,
+    // when each card's utilization dropped to 0
+    idle_since: HashMap<String, Instant>,
+    // why there are no readings, cleared by the next good line
+    error: Option<HwError>,
+}
+
+impl StreamState {
+    fn wanted(&self) -> bool {
+        self.asleep.values().any(|asleep| !asleep)
+    }
+
+    // every awake card has been idle for IDLE_STOP
+    fn idle(&self, now: Instant) -> bool {
+        self.wanted()
+            && self
+                .asleep
+                .iter()
+                .filter(|(_, asleep)| !**asleep)
+                .all(|(key, _)| {
+                    self.idle_since
+                        .get(key)
+                        .is_some_and(|since| now.duration_since(*since) >= IDLE_STOP)
+                })
+    }
+
+    fn stop(&mut self, why: Stop) {
+        if let Some(mut child) = self.child.take() {
+            let _ = child.kill();
+            let _ = child.wait();
+            self.stopped = Some(why);
+        }
+    }
+}
+
+// The nvidia-smi child and what it printed last. Anything that talks to the
+// driver keeps the card awake, so the child only runs while some card is:
+//
+// - it's spawned on the first read, so a card that's runtime suspended at
+//   startup (and never read) isn't woken up by it
+// - it's killed once every card reports runtime suspended, and spawned again
+//   on the next read
+// - a card can't get to suspended while it's being polled, so once every card
+//   has been idle for IDLE_STOP the child is paused for REST, leaving the
+//   driver a window to put them to sleep. The last readings stay up meanwhile.
+#[derive(Default)]
+pub struct NvidiaStream {
+    state: Mutex<StreamState>,
+    // signalled when a card wakes up or goes to sleep
+    changed: Condvar,
+}
+
+impl NvidiaStream {
+    // runs nvidia-smi until it exits or is stopped, returns why it exited
+    fn run_child(&self) -> HwError {
+        let mut child = match Command::new("nvidia-smi")
+            .args([
+                &format!("--query-gpu={}", QUERY),
+                "--format=csv,noheader,nounits",
+                &format!("--loop-ms={}", PERIOD_MS),
+            ])
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+        {
+            Ok(child) => child,
+            Err(err) => return HwError::from_spawn(err, "nvidia-smi"),
+        };
+        info!(pid = child.id(), "nvidia-smi started");
+
+        // drained as it comes so a chatty child can't block on a full pipe,
+        // the last line is kept for the error
+        let stderr = child.stderr.take().map(|pipe| {
+            thread::spawn(move || {
+                let mut last = String::new();
+                for line in BufReader::new(pipe).lines().map_while(Result::ok) {
+                    if !line.trim().is_empty() {
+                        warn!("nvidia-smi: {}", line.trim());
+                        last = line;
+                    }
+                }
+                last
+            })
+        });
+        let stdout = child.stdout.take();
+        self.state.lock().unwrap().child = Some(child);
+
+        for line in stdout
+            .into_iter()
+            .flat_map(|pipe| BufReader::new(pipe).lines().map_while(Result::ok))
+        {
+            let now = Instant::now();
+            let mut state = self.state.lock().unwrap();
+
+            for parsed in parse_csv(&line) {
+                match parsed {
+                    Ok((key, reading)) => {
+                        if reading.busy == Some(0.) {
+                            state.idle_since.entry(key.clone()).or_insert(now);
+                        } else {
+                            state.idle_since.remove(&key);
+                        }
+                        state.readings.insert(key, reading);
+                        state.error = None;
+                    }
+                    Err(err) => {
+                        warn!(%err, "unparsable nvidia-smi line");
+                        state.error = Some(err);
+                    }
+                }
+            }
+
+            if state.idle(now) {
+                state.stop(Stop::Idle);
+            }
+        }
+
+        // still there if it exited on its own
+        let status = self
+            .state
+            .lock()
+            .unwrap()
+            .child
+            .take()
+            .map(|mut child| child.wait());
+        let stderr = stderr
+            .and_then(|thread| thread.join().ok())
+            .unwrap_or_default();
+
+        HwError::CommandFailed {
+            command: "nvidia-smi".to_string(),
+            stderr: match (stderr.trim().is_empty(), status) {
+                (false, _) => stderr,
+                (true, Some(Ok(status))) => format!("exited ({})", status),
+                (true, Some(Err(err))) => err.to_string(),
+                (true, None) => "stopped".to_string(),
+            },
+        }
+    }
+
+    // spawns the child whenever a card wants reading, until the process exits
+    fn supervise(&self) {
+        let mut rest_until: Option<Instant> = None;
+
+        loop {
+            {
+                let mut state = self.state.lock().unwrap();
+                loop {
+                    let resting = rest_until
+                        .map(|until| until.saturating_duration_since(Instant::now()))
+                        .filter(|left| !left.is_zero());
+
+                    state = match (state.wanted(), resting) {
+                        (true, None) => break,
+                        (true, Some(left)) => self.changed.wait_timeout(state, left).unwrap().0,
+                        (false, _) => self.changed.wait(state).unwrap(),
+                    };
+                }
+            }
+
+            let err = self.run_child();
+
+            let mut state = self.state.lock().unwrap();
+            rest_until = match state.stopped.take() {
+                Some(Stop::Asleep) => {
+                    info!("nvidia-smi stopped, every card is asleep");
+                    state.readings.clear();
+                    state.idle_since.clear();
+                    None
+                }
+                Some(Stop::Idle) => {
+                    info!("nvidia-smi paused for {:?} so idle cards can sleep", REST);
+                    state.idle_since.clear();
+                    Some(Instant::now() + REST)
+                }
+                None => {
+                    warn!(%err, "nvidia-smi is gone, restarting in {:?}", RESTART_DELAY);
+                    // old readings would look live forever otherwise
+                    state.readings.clear();
+                    state.idle_since.clear();
+                    state.error = Some(err);
+                    Some(Instant::now() + RESTART_DELAY)
+                }
+            };
+        }
+    }
+
+    // the latest reading, never waits for one: until the first line is in
+    // it's a missing reading
+    pub fn read(self: &Arc<Self>, pci: &str) -> HwResult<GpuReading> {
+        let key = pci_key(pci);
+
+        let mut state = self.state.lock().unwrap();
+        if state.asleep.insert(key.clone(), false) != Some(false) {
+            self.changed.notify_all();
+        }
+        if !state.started {
+            state.started = true;
+            let stream = self.clone();
+            thread::spawn(move || stream.supervise());
+        }
+
+        match (state.readings.get(&key), &state.error) {
+            (Some(reading), _) => Ok(reading.clone()),
+            (None, Some(err)) => Err(err.clone()),
+            (None, None) => Err(HwError::missing(format!(
+                "nvidia-smi reading for {} (none yet)",
+                pci
+            ))),
+        }
+    }
+
+    // what gpu::status tells instead of reading while the card sleeps
+    pub fn suspended(&self, pci: &str) {
+        let key = pci_key(pci);
+
+        let mut state = self.state.lock().unwrap();
+        if state.asleep.insert(key.clone(), true) == Some(true) {
+            return;
+        }
+        state.readings.remove(&key);
+        state.idle_since.remove(&key);
+        if !state.wanted() {
+            state.stop(Stop::Asleep);
+        }
+        self.changed.notify_all();
+    }
+}
+
+pub struct NvidiaSmiGpu {
+    card: GpuCard,
+    stream: Arc<NvidiaStream>,
+}
+
+impl NvidiaSmiGpu {
+    pub fn new(card: GpuCard, stream: Arc<NvidiaStream>) -> Self {
+        Self { card, stream }
+    }
+}
+
+impl GpuProvider for NvidiaSmiGpu {
+    fn card(&self) -> &GpuCard {
+        &self.card
+    }
+
+    fn read(&self) -> HwResult<GpuReading> {
+        self.stream.read(&self.card.pci)
+    }
+
+    fn suspended(&self) {
+        self.stream.suspended(&self.card.pci);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const LINE: &str = "00000000:01:00.0, 45, 12, 512, 8192, 10.52, 300, 405, 30, P8";
+
+    #[test]
+    fn pci_key_matches_sysfs() {
+        assert_eq!(pci_key("00000000:01:00.0"), "0000:01:00.0");
+        assert_eq!(pci_key(" 00000000:0A:00.0 "), "0000:0a:00.0");
+        assert_eq!(pci_key("0000:01:00.0"), "0000:01:00.0");
+        assert_eq!(pci_key("00000001:01:00.0"), "0001:01:00.0");
+        // not a bus id at all, left as it is
+        assert_eq!(pci_key("gpu0"), "gpu0");
+    }
+
+    #[test]
+    fn parses_a_full_line() {
+        let (key, reading) = parse_line(LINE).unwrap();
+
+        assert_eq!(key, "0000:01:00.0");
+        assert_eq!(
+            reading,
+            GpuReading {
+                busy: Some(12.),
+                temp: Some(45.),
+                vram_used: Some(512),
+                vram_total: Some(8192),
+                sclk: Some(300),
+                mclk: Some(405),
+                power: Some(10.52),
+                fan: Some(30.),
+                pstate: Some("P8".to_string()),
+            }
+        );
+    }
+
+    #[test]
+    fn unreported_fields_are_none() {
+        let line =
+            "00000000:01:00.0, 45, [N/A], 512, 8192, [Not Supported], 300, 405, [N/A], [N/A]";
+        let (_, reading) = parse_line(line).unwrap();
+
+        assert_eq!(reading.temp, Some(45.));
+        assert_eq!(reading.busy, None);
+        assert_eq!(reading.power, None);
+        assert_eq!(reading.fan, None);
+        assert_eq!(reading.pstate, None);
+    }
+
+    #[test]
+    fn short_rows_are_errors() {
+        let line = "00000000:01:00.0, 45, 12";
+        assert_eq!(
+            parse_line(line),
+            Err(HwError::parse("nvidia-smi output", line))
+        );
+        assert!(parse_line("").is_err());
+    }
+
+    #[test]
+    fn csv_skips_headers_and_blank_lines() {
+        let text = format!(
+            "pci.bus_id, temperature.gpu, utilization.gpu [%]\n\n{}\n00000000:02:00.0, 50\n",
+            LINE
+        );
+        let parsed = parse_csv(&text);
+
+        assert_eq!(parsed.len(), 2);
+        assert_eq!(parsed[0].as_ref().unwrap().0, "0000:01:00.0");
+        assert!(parsed[1].is_err());
+    }
+}
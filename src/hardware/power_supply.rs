@@ -0,0 +1,233 @@
+// Batteries and AC adapters under /sys/class/power_supply.
+
+use std::{fmt, path::Path, time::Duration};
+
+use crate::hardware::{
+    attr::{read_attr, read_value, write_attr},
+    error::{HwError, HwResult},
+    root::SysRoot,
+};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ChargeThreshold {
+    Start,
+    End,
+}
+
+impl ChargeThreshold {
+    fn attr(self) -> &'static str {
+        match self {
+            ChargeThreshold::Start => "charge_control_start_threshold",
+            ChargeThreshold::End => "charge_control_end_threshold",
+        }
+    }
+}
+
+impl fmt::Display for ChargeThreshold {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ChargeThreshold::Start => write!(f, "start"),
+            ChargeThreshold::End => write!(f, "end"),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Battery {
+    // "BAT0"
+    pub name: String,
+    pub model: String,
+    // "Charging", "Discharging", "Full", "Not charging", ...
+    pub status: String,
+    pub capacity: u8,
+    // Wh, converted from charge_* (µAh) when the battery only reports those
+    pub energy_now: f64,
+    pub energy_full: f64,
+    pub energy_full_design: f64,
+    // W, always positive
+    pub power_now: f64,
+    pub cycle_count: Option<u32>,
+    // percent, None when the firmware doesn't expose them
+    pub charge_start: Option<u8>,
+    pub charge_end: Option<u8>,
+}
+
+impl Battery {
+    // how much of the design capacity is gone, in percent
+    pub fn wear(&self) -> Option<f64> {
+        if self.energy_full_design <= 0. {
+            return None;
+        }
+        Some((1. - self.energy_full / self.energy_full_design).max(0.) * 100.)
+    }
+
+    pub fn time_to_empty(&self) -> Option<Duration> {
+        if self.status != "Discharging" || self.power_now <= 0. {
+            return None;
+        }
+        Some(Duration::from_secs_f64(
+            self.energy_now / self.power_now * 3600.,
+        ))
+    }
+
+    // to energy_full, which already takes a charge_end threshold into account
+    // on most firmwares only approximately, so this is an estimate at best
+    pub fn time_to_full(&self) -> Option<Duration> {
+        if self.status != "Charging" || self.power_now <= 0. {
+            return None;
+        }
+        let missing = (self.energy_full - self.energy_now).max(0.);
+        Some(Duration::from_secs_f64(missing / self.power_now * 3600.))
+    }
+
+    pub fn threshold(&self, which: ChargeThreshold) -> Option<u8> {
+        match which {
+            ChargeThreshold::Start => self.charge_start,
+            ChargeThreshold::End => self.charge_end,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct PowerSupplies {
+    pub batteries: Vec<Battery>,
+    // batteries that are there but couldn't be read, by name
+    pub unreadable: Vec<(String, HwError)>,
+    // None when there is no Mains supply at all
+    pub ac_online: Option<bool>,
+}
+
+// µ-units in sysfs
+fn micro(dir: &Path, name: &str) -> Option<f64> {
+    read_value::<f64>(&dir.join(name))
+        .ok()
+        .map(|value| value / 1_000_000.)
+}
+
+fn read_battery(dir: &Path, name: String) -> HwResult<Battery> {
+    let status = read_attr(&dir.join("status"))?;
+    let capacity = read_value(&dir.join("capacity"))?;
+
+    // energy_* in µWh, or charge_* in µAh that need a voltage to become Wh
+    let voltage = micro(dir, "voltage_min_design").or_else(|| micro(dir, "voltage_now"));
+    let energy = |kind: &str| -> Option<f64> {
+        micro(dir, &format!("energy_{}", kind))
+            .or_else(|| Some(micro(dir, &format!("charge_{}", kind))? * voltage?))
+    };
+
+    let power_now = micro(dir, "power_now")
+        .or_else(|| Some(micro(dir, "current_now")? * micro(dir, "voltage_now")?))
+        .unwrap_or(0.)
+        .abs();
+
+    Ok(Battery {
+        model: read_attr(&dir.join("model_name")).unwrap_or_default(),
+        status,
+        capacity,
+        energy_now: energy("now").unwrap_or(0.),
+        energy_full: energy("full").unwrap_or(0.),
+        energy_full_design: energy("full_design").unwrap_or(0.),
+        power_now,
+        // some firmwares report 0 when they don't count
+        cycle_count: read_value(&dir.join("cycle_count"))
+            .ok()
+            .filter(|count| *count > 0),
+        charge_start: read_value(&dir.join(ChargeThreshold::Start.attr())).ok(),
+        charge_end: read_value(&dir.join(ChargeThreshold::End.attr())).ok(),
+        name,
+    })
+}
+
+pub fn scan(root: &SysRoot) -> HwResult<PowerSupplies> {
+    let mut supplies = PowerSupplies::default();
+
+    for dir in root.sys_glob("class/power_supply/*") {
+        let Some(name) = dir.file_name().map(|n| n.to_string_lossy().to_string()) else {
+            continue;
+        };
+
+        match read_attr(&dir.join("type")).as_deref() {
+            // scope=Device is a mouse or headset battery, not ours
+            Ok("Battery") if read_attr(&dir.join("scope")).as_deref() != Ok("Device") => {
+                match read_battery(&dir, name.clone()) {
+                    Ok(battery) => supplies.batteries.push(battery),
+                    Err(err) => supplies.unreadable.push((name, err)),
+                }
+            }
+            Ok("Mains") => {
+                let online = read_value::<u8>(&dir.join("online")).is_ok_and(|v| v == 1);
+                supplies.ac_online = Some(supplies.ac_online.unwrap_or(false) || online);
+            }
+            _ => {}
+        }
+    }
+
+    if supplies.batteries.is_empty()
+        && supplies.unreadable.is_empty()
+        && supplies.ac_online.is_none()
+    {
+        return Err(HwError::missing("power supplies"));
+    }
+
+    Ok(supplies)
+}
+
+pub fn set_threshold(
+    root: &SysRoot,
+    battery: &str,
+    which: ChargeThreshold,
+    percent: u8,
+) -> HwResult<()> {
+    let path = root.sys(&format!("class/power_supply/{}/{}", battery, which.attr()));
+    if !path.exists() {
+        return Err(HwError::missing(format!(
+            "{} charge {} threshold",
+            battery, which
+        )));
+    }
+
+    write_attr(&path, &percent.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{env, fs, process};
+
+    use super::*;
+
+    fn write(dir: &Path, attrs: &[(&str, &str)]) {
+        fs::create_dir_all(dir).unwrap();
+        for (name, value) in attrs {
+            fs::write(dir.join(name), value).unwrap();
+        }
+    }
+
+    #[test]
+    fn unreadable_batteries_dont_fail_the_scan() {
+        let root = env::temp_dir().join(format!("my-template-power-{}", process::id()));
+        let class = root.join("sys/class/power_supply");
+        write(&class.join("AC"), &[("type", "Mains"), ("online", "1")]);
+        write(
+            &class.join("BAT0"),
+            &[
+                ("type", "Battery"),
+                ("status", "Charging"),
+                ("capacity", "64"),
+            ],
+        );
+        // no capacity
+        write(
+            &class.join("BAT1"),
+            &[("type", "Battery"), ("status", "Full")],
+        );
+
+        let supplies = scan(&SysRoot::new(&root)).unwrap();
+        fs::remove_dir_all(&root).unwrap();
+
+        assert_eq!(supplies.ac_online, Some(true));
+        assert_eq!(supplies.batteries.len(), 1);
+        assert_eq!(supplies.batteries[0].capacity, 64);
+        assert_eq!(supplies.unreadable.len(), 1);
+        assert_eq!(supplies.unreadable[0].0, "BAT1");
+    }
+}
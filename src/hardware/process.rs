@@ -0,0 +1,190 @@
+// Running processes, for the Processes page. Not hardware, but it comes out
+// of /proc like cpu usage so it lives with the backends.
+
+use std::{collections::HashMap, fs};
+
+use crate::hardware::{
+    error::{HwError, HwResult},
+    procstat::CpuTimes,
+    root::SysRoot,
+};
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ProcessInfo {
+    pub pid: u32,
+    pub name: String,
+    // percent of one core, so it can go past 100 for threaded processes
+    pub cpu: f32,
+    // resident, bytes
+    pub memory: u64,
+}
+
+// busiest first, then biggest, pid as the tie breaker so rows don't jump
+pub fn sort_by_usage(processes: &mut [ProcessInfo]) {
+    processes.sort_by(|a, b| {
+        b.cpu
+            .total_cmp(&a.cpu)
+            .then(b.memory.cmp(&a.memory))
+            .then(a.pid.cmp(&b.pid))
+    });
+}
+
+// what we use of /proc/<pid>/stat
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct PidStat {
+    pub pid: u32,
+    // comm, cut to 15 bytes by the kernel
+    pub name: String,
+    // utime + stime, in jiffies
+    pub jiffies: u64,
+    pub rss_pages: u64,
+}
+
+impl PidStat {
+    // "812 (tmux: server) S 1 812 ...", comm may hold spaces and parens so
+    // it ends at the last ')'; utime, stime and rss are fields 14, 15 and 24
+    pub fn parse(text: &str) -> HwResult<Self> {
+        let what = "/proc/<pid>/stat";
+        let bad = || HwError::parse(what, text.trim());
+
+        let (pid, rest) = text.split_once(" (").ok_or_else(bad)?;
+        let (name, rest) = rest.rsplit_once(')').ok_or_else(bad)?;
+        // fields from 3 (state) on
+        let fields: Vec<&str> = rest.split_whitespace().collect();
+        let field = |n: usize| -> HwResult<u64> {
+            fields
+                .get(n - 3)
+                .and_then(|field| field.parse().ok())
+                .ok_or_else(bad)
+        };
+
+        Ok(Self {
+            pid: pid.trim().parse().map_err(|_| bad())?,
+            name: name.to_string(),
+            jiffies: field(14)? + field(15)?,
+            rss_pages: field(24)?,
+        })
+    }
+}
+
+// every process under <root>/proc, skipping the ones that exit while we read
+pub fn scan(root: &SysRoot) -> HwResult<Vec<PidStat>> {
+    let dir = root.proc("");
+    let entries = fs::read_dir(&dir).map_err(|err| HwError::from_io(err, &dir))?;
+
+    Ok(entries
+        .filter_map(Result::ok)
+        .filter(|entry| {
+            entry
+                .file_name()
+                .to_str()
+                .is_some_and(|name| name.parse::<u32>().is_ok())
+        })
+        .filter_map(|entry| fs::read_to_string(entry.path().join("stat")).ok())
+        .filter_map(|text| PidStat::parse(&text).ok())
+        .collect())
+}
+
+pub fn page_size() -> u64 {
+    let size = unsafe { libc::sysconf(libc::_SC_PAGESIZE) };
+    u64::try_from(size).unwrap_or(4096)
+}
+
+// cpu time of every process at the last read, usage comes from the next one
+#[derive(Debug, Default)]
+pub struct ProcessTimes {
+    all: u64,
+    pids: HashMap<u32, u64>,
+}
+
+impl ProcessTimes {
+    // `all` is the "cpu" line of /proc/stat read along with `stats`, adding up
+    // `cores` cores; processes we haven't seen before show 0% for one read
+    pub fn update(
+        &mut self,
+        all: CpuTimes,
+        cores: usize,
+        stats: Vec<PidStat>,
+        page_size: u64,
+    ) -> Vec<ProcessInfo> {
+        // jiffies a single core went through since the last read
+        let elapsed = all.total.saturating_sub(self.all) as f32 / cores.max(1) as f32;
+
+        let processes = stats
+            .iter()
+            .map(|stat| {
+                let cpu = match self.pids.get(&stat.pid) {
+                    Some(old) if elapsed > 0. => {
+                        stat.jiffies.saturating_sub(*old) as f32 / elapsed * 100.
+                    }
+                    _ => 0.,
+                };
+                ProcessInfo {
+                    pid: stat.pid,
+                    name: stat.name.clone(),
+                    cpu,
+                    memory: stat.rss_pages * page_size,
+                }
+            })
+            .collect();
+
+        self.all = all.total;
+        self.pids = stats
+            .into_iter()
+            .map(|stat| (stat.pid, stat.jiffies))
+            .collect();
+        processes
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn stat(pid: u32, jiffies: u64) -> PidStat {
+        PidStat {
+            pid,
+            name: format!("proc{}", pid),
+            jiffies,
+            rss_pages: 10,
+        }
+    }
+
+    #[test]
+    fn comm_may_hold_spaces_and_parens() {
+        let text = "812 (tmux: (server)) S 1 812 812 0 -1 4194560 2315 0 0 0 \
+                    150 30 0 0 20 0 1 0 3070 23003136 1234 18446744073709551615\n";
+
+        assert_eq!(
+            PidStat::parse(text),
+            Ok(PidStat {
+                pid: 812,
+                name: "tmux: (server)".to_string(),
+                jiffies: 180,
+                rss_pages: 1234,
+            })
+        );
+    }
+
+    #[test]
+    fn short_lines_are_errors() {
+        assert!(PidStat::parse("812 (tmux) S 1 812").is_err());
+        assert!(PidStat::parse("garbage").is_err());
+    }
+
+    #[test]
+    fn usage_is_per_core() {
+        let mut times = ProcessTimes::default();
+        let all = |total| CpuTimes { busy: 0, total };
+
+        // nothing to compare against yet
+        let first = times.update(all(1000), 2, vec![stat(1, 100), stat(2, 0)], 4096);
+        assert!(first.iter().all(|process| process.cpu == 0.));
+        assert_eq!(first[0].memory, 40960);
+
+        // 200 jiffies over two cores is 100 per core
+        let second = times.update(all(1200), 2, vec![stat(1, 150), stat(3, 50)], 4096);
+        assert_eq!(second[0].cpu, 50.);
+        assert_eq!(second[1].cpu, 0.);
+    }
+}
@@ -0,0 +1,126 @@
+// Cpu time counters from /proc/stat. They only ever go up, usage is the
+// difference between two reads.
+
+use crate::hardware::error::{HwError, HwResult};
+
+// in jiffies since boot
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct CpuTimes {
+    pub busy: u64,
+    pub total: u64,
+}
+
+impl CpuTimes {
+    // "cpu0 4705 356 584 3699 23 23 0 0 0 0": user nice system idle iowait irq
+    // softirq steal, then guest and guest_nice which user / nice already count
+    fn parse(fields: &str) -> Option<Self> {
+        let times: Vec<u64> = fields
+            .split_whitespace()
+            .take(8)
+            .map(|field| field.parse().ok())
+            .collect::<Option<_>>()?;
+        if times.len() < 4 {
+            return None;
+        }
+
+        let total = times.iter().sum();
+        let idle = times[3] + times.get(4).copied().unwrap_or(0);
+        Some(Self {
+            busy: total - idle,
+            total,
+        })
+    }
+
+    // percent of the time since `old` spent busy, 0 when no time passed
+    pub fn usage_since(&self, old: &CpuTimes) -> f32 {
+        let total = self.total.saturating_sub(old.total);
+        if total == 0 {
+            return 0.;
+        }
+        let busy = self.busy.saturating_sub(old.busy).min(total);
+        busy as f32 / total as f32 * 100.
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Stat {
+    // the "cpu" line, every core added up
+    pub all: CpuTimes,
+    // "cpu0", "cpu1", ... in order
+    pub cores: Vec<CpuTimes>,
+}
+
+impl Stat {
+    pub fn parse(text: &str) -> HwResult<Self> {
+        let mut all = None;
+        let mut cores = Vec::new();
+
+        for line in text.lines() {
+            let Some((label, fields)) = line.split_once(char::is_whitespace) else {
+                continue;
+            };
+            let Some(core) = label.strip_prefix("cpu") else {
+                continue;
+            };
+            let times = CpuTimes::parse(fields)
+                .ok_or_else(|| HwError::parse(format!("{} in /proc/stat", label), line))?;
+
+            match core {
+                "" => all = Some(times),
+                _ => cores.push(times),
+            }
+        }
+
+        let all = all.ok_or_else(|| HwError::missing("cpu line in /proc/stat"))?;
+        Ok(Self { all, cores })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const STAT: &str = "cpu  300 0 100 560 40 0 0 0 0 0
+cpu0 200 0 50 240 10 0 0 0 0 0
+cpu1 100 0 50 320 30 0 0 0 0 0
+intr 12345 0 0
+ctxt 6789
+";
+
+    #[test]
+    fn parses_every_core() {
+        let stat = Stat::parse(STAT).unwrap();
+
+        assert_eq!(
+            stat.all,
+            CpuTimes {
+                busy: 400,
+                total: 1000
+            }
+        );
+        assert_eq!(stat.cores.len(), 2);
+        assert_eq!(stat.cores[0].busy, 250);
+        assert_eq!(stat.cores[1].total, 500);
+    }
+
+    #[test]
+    fn usage_is_the_busy_share_of_the_delta() {
+        let old = CpuTimes {
+            busy: 250,
+            total: 500,
+        };
+        let new = CpuTimes {
+            busy: 325,
+            total: 600,
+        };
+
+        assert_eq!(new.usage_since(&old), 75.);
+        assert_eq!(old.usage_since(&old), 0.);
+    }
+
+    #[test]
+    fn garbage_is_an_error() {
+        assert!(Stat::parse("cpu  1 2 x 4\n").is_err());
+        assert!(Stat::parse("intr 1 2\n").is_err());
+    }
+}
@@ -0,0 +1,138 @@
+// CPU energy counters from the powercap RAPL interface (intel-rapl, and AMD's
+// rapl driver which registers under the same name).
+
+use std::time::Duration;
+
+use crate::hardware::{
+    attr::{read_attr, read_value},
+    error::{HwError, HwResult},
+    root::SysRoot,
+};
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct RaplZone {
+    // "intel-rapl:0", "intel-rapl:0:1"
+    pub id: String,
+    // "package-0", "core", "uncore", "dram", "psys"
+    pub name: String,
+    pub energy_uj: u64,
+    // energy_uj wraps back to 0 after this
+    pub max_energy_range_uj: u64,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct ZonePower {
+    pub name: String,
+    pub watts: f64,
+}
+
+// every zone and subzone, sorted by id so package comes before its parts.
+// energy_uj is root only on most kernels, that error is passed on as is
+pub fn scan_zones(root: &SysRoot) -> HwResult<Vec<RaplZone>> {
+    let dirs = root.sys_glob("class/powercap/intel-rapl:*");
+    if dirs.is_empty() {
+        return Err(HwError::missing("powercap intel-rapl"));
+    }
+
+    dirs.iter()
+        .map(|dir| {
+            Ok(RaplZone {
+                id: dir
+                    .file_name()
+                    .map(|name| name.to_string_lossy().to_string())
+                    .unwrap_or_default(),
+                name: read_attr(&dir.join("name"))?,
+                energy_uj: read_value(&dir.join("energy_uj"))?,
+                max_energy_range_uj: read_value(&dir.join("max_energy_range_uj"))?,
+            })
+        })
+        .collect()
+}
+
+// µJ used between two reads of the same counter
+pub fn energy_delta(prev: u64, next: u64, max_range: u64) -> u64 {
+    if next >= prev {
+        next - prev
+    } else {
+        // wrapped around
+        max_range.saturating_sub(prev) + next
+    }
+}
+
+// average watts of each zone between two scans, zones missing from `prev`
+// (e.g. the very first scan) are left out
+pub fn power_between(prev: &[RaplZone], next: &[RaplZone], elapsed: Duration) -> Vec<ZonePower> {
+    let seconds = elapsed.as_secs_f64();
+    if seconds <= 0. {
+        return Vec::new();
+    }
+
+    next.iter()
+        .filter_map(|zone| {
+            let old = prev.iter().find(|old| old.id == zone.id)?;
+            let uj = energy_delta(old.energy_uj, zone.energy_uj, zone.max_energy_range_uj);
+
+            Some(ZonePower {
+                name: zone.name.clone(),
+                watts: uj as f64 / 1_000_000. / seconds,
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RANGE: u64 = 262_143_328_850;
+
+    fn zone(id: &str, name: &str, energy_uj: u64) -> RaplZone {
+        RaplZone {
+            id: id.to_string(),
+            name: name.to_string(),
+            energy_uj,
+            max_energy_range_uj: RANGE,
+        }
+    }
+
+    #[test]
+    fn delta_without_a_wrap() {
+        assert_eq!(energy_delta(1_000, 16_000, RANGE), 15_000);
+        assert_eq!(energy_delta(5, 5, RANGE), 0);
+    }
+
+    #[test]
+    fn delta_across_a_wrap() {
+        assert_eq!(energy_delta(RANGE - 1_000, 4_000, RANGE), 5_000);
+    }
+
+    #[test]
+    fn delta_without_a_range_counts_from_zero() {
+        assert_eq!(energy_delta(9_000, 4_000, 0), 4_000);
+    }
+
+    #[test]
+    fn power_of_zones_seen_twice() {
+        let prev = [zone("intel-rapl:0", "package-0", RANGE - 2_000_000)];
+        let next = [
+            zone("intel-rapl:0", "package-0", 8_000_000),
+            // only in the second scan
+            zone("intel-rapl:0:0", "core", 3_000_000),
+        ];
+
+        // 10 J over 2 s
+        assert_eq!(
+            power_between(&prev, &next, Duration::from_secs(2)),
+            [ZonePower {
+                name: "package-0".to_string(),
+                watts: 5.,
+            }]
+        );
+    }
+
+    #[test]
+    fn no_power_without_elapsed_time() {
+        let zones = [zone("intel-rapl:0", "package-0", 1_000)];
+        assert!(power_between(&zones, &zones, Duration::ZERO).is_empty());
+    }
+}
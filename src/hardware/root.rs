@@ -0,0 +1,62 @@
+use std::{
+    env,
+    path::{Path, PathBuf},
+};
+
+use glob::{Pattern, glob};
+
+pub const ROOT_ENV: &str = "HP_WMI_ROOT";
+
+// Everything that touches /sys goes through this, so the whole panel can be
+// pointed at a fake tree (see fixtures/) instead of the real machine.
+#[derive(Clone, Debug)]
+pub struct SysRoot {
+    root: PathBuf,
+}
+
+impl SysRoot {
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        Self { root: root.into() }
+    }
+
+    // cli flag wins, then the env var, then the real root
+    pub fn resolve(flag: Option<PathBuf>) -> Self {
+        let root = flag
+            .or_else(|| env::var_os(ROOT_ENV).map(PathBuf::from))
+            .unwrap_or_else(|| PathBuf::from("/"));
+
+        Self::new(root)
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.root
+    }
+
+    pub fn is_real(&self) -> bool {
+        self.root == Path::new("/")
+    }
+
+    // e.g. sys("class/hwmon") -> <root>/sys/class/hwmon
+    pub fn sys(&self, rel: &str) -> PathBuf {
+        self.root.join("sys").join(rel)
+    }
+
+    // e.g. proc("meminfo") -> <root>/proc/meminfo
+    pub fn proc(&self, rel: &str) -> PathBuf {
+        self.root.join("proc").join(rel)
+    }
+
+    // all matches of a glob relative to <root>/sys, sorted
+    pub fn sys_glob(&self, pattern: &str) -> Vec<PathBuf> {
+        let base = Pattern::escape(&self.sys("").display().to_string());
+
+        match glob(&format!("{}{}", base, pattern)) {
+            Ok(paths) => paths.filter_map(Result::ok).collect(),
+            Err(_) => Vec::new(),
+        }
+    }
+
+    pub fn sys_glob_first(&self, pattern: &str) -> Option<PathBuf> {
+        self.sys_glob(pattern).into_iter().next()
+    }
+}
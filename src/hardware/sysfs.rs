@@ -0,0 +1,331 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    sync::Mutex,
+};
+
+use tracing::{info, instrument};
+
+use crate::hardware::{
+    FanInfo, FanMode, HardwareBackend,
+    attr::{read_attr, read_value, write_attr},
+    cpufreq::{self, CoreFreq, CpuPolicy},
+    error::{HwError, HwResult},
+    gpu::{self, GpuProvider, GpuStatus},
+    hwmon::{self, TempSensor},
+    meminfo::MemInfo,
+    power_supply::{self, ChargeThreshold, PowerSupplies},
+    process::{self, ProcessInfo, ProcessTimes},
+    procstat::Stat,
+    rapl::{self, RaplZone},
+    root::SysRoot,
+};
+
+struct FanPath {
+    info: FanInfo,
+    // hwmon directory and the N in fan{N}_*
+    dir: PathBuf,
+    index: usize,
+}
+
+pub struct SysfsBackend {
+    root: SysRoot,
+    // /proc/stat at the last cpu_usage read
+    cpu_times: Mutex<Option<Stat>>,
+    process_times: Mutex<ProcessTimes>,
+    page_size: u64,
+    fans: Vec<FanPath>,
+    gpus: Vec<Box<dyn GpuProvider>>,
+}
+
+impl SysfsBackend {
+    // with `all_fan_chips` fans of every hwmon chip are picked up, not just hp-wmi's
+    pub fn new(root: SysRoot, all_fan_chips: bool) -> Self {
+        let fans = discover_fans(&root, all_fan_chips);
+        for fan in &fans {
+            info!(dir = %fan.dir.display(), "found fan {} ({})", fan.info.id, fan.info.label);
+        }
+        let gpus = gpu::discover(&root);
+
+        Self {
+            root,
+            cpu_times: Mutex::new(None),
+            process_times: Mutex::new(ProcessTimes::default()),
+            page_size: process::page_size(),
+            fans,
+            gpus,
+        }
+    }
+
+    fn fan_file(&self, fan: usize, suffix: &str) -> HwResult<PathBuf> {
+        let fan = self
+            .fans
+            .iter()
+            .find(|path| path.info.id == fan)
+            .ok_or_else(|| HwError::missing(format!("fan {}", fan)))?;
+
+        Ok(fan.dir.join(format!("fan{}_{}", fan.index, suffix)))
+    }
+
+    fn stat(&self) -> HwResult<Stat> {
+        let path = self.root.proc("stat");
+        let text = fs::read_to_string(&path).map_err(|err| HwError::from_io(err, &path))?;
+
+        Stat::parse(&text)
+    }
+
+    fn hp_wmi_file(&self, name: &str) -> HwResult<PathBuf> {
+        let pattern = format!("devices/platform/hp-wmi/hwmon/hwmon*/{}", name);
+
+        self.root
+            .sys_glob_first(&pattern)
+            .ok_or_else(|| HwError::missing(format!("/sys/{}", pattern)))
+    }
+}
+
+// N of every fan{N}_input in a hwmon dir, sorted
+fn fan_indexes(dir: &Path) -> Vec<usize> {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return Vec::new();
+    };
+
+    let mut indexes: Vec<usize> = entries
+        .filter_map(Result::ok)
+        .filter_map(|entry| {
+            let name = entry.file_name().to_string_lossy().to_string();
+            name.strip_prefix("fan")?
+                .strip_suffix("_input")?
+                .parse()
+                .ok()
+        })
+        .collect();
+
+    indexes.sort();
+    indexes
+}
+
+fn discover_fans(root: &SysRoot, all_chips: bool) -> Vec<FanPath> {
+    let mut dirs = root.sys_glob("devices/platform/hp-wmi/hwmon/hwmon*");
+
+    if all_chips {
+        // class/hwmon entries are symlinks into devices/, skip the ones we have
+        let known: Vec<PathBuf> = dirs
+            .iter()
+            .filter_map(|dir| fs::canonicalize(dir).ok())
+            .collect();
+
+        for dir in root.sys_glob("class/hwmon/hwmon*") {
+            match fs::canonicalize(&dir) {
+                Ok(real) if known.contains(&real) => {}
+                _ => dirs.push(dir),
+            }
+        }
+    }
+
+    let mut fans = Vec::new();
+    for dir in dirs {
+        let chip = read_attr(&dir.join("name")).unwrap_or_else(|_| "hwmon".to_string());
+
+        for index in fan_indexes(&dir) {
+            let label = read_attr(&dir.join(format!("fan{}_label", index)))
+                .ok()
+                .filter(|label| !label.is_empty())
+                .unwrap_or_else(|| match chip.as_str() {
+                    "hp" => format!("fan {}", index),
+                    _ => format!("{} fan {}", chip, index),
+                });
+
+            fans.push(FanPath {
+                info: FanInfo {
+                    id: fans.len() + 1,
+                    label,
+                    chip: chip.clone(),
+                },
+                dir: dir.clone(),
+                index,
+            });
+        }
+    }
+
+    fans
+}
+
+impl HardwareBackend for SysfsBackend {
+    fn fans(&self) -> HwResult<Vec<FanInfo>> {
+        Ok(self.fans.iter().map(|fan| fan.info.clone()).collect())
+    }
+
+    fn fan_speed(&self, fan: usize) -> HwResult<u16> {
+        read_value(&self.fan_file(fan, "input")?)
+    }
+
+    fn fan_max(&self, fan: usize) -> HwResult<u16> {
+        read_value(&self.fan_file(fan, "max")?)
+    }
+
+    fn fan_target(&self, fan: usize) -> HwResult<u16> {
+        read_value(&self.fan_file(fan, "target")?)
+    }
+
+    #[instrument(level = "debug", skip(self))]
+    fn set_fan_target(&self, fan: usize, rpm: u16) -> HwResult<()> {
+        write_attr(&self.fan_file(fan, "target")?, &rpm.to_string())
+    }
+
+    fn fan_mode(&self) -> HwResult<FanMode> {
+        FanMode::from_pwm_enable(&read_attr(&self.hp_wmi_file("pwm1_enable")?)?)
+    }
+
+    #[instrument(level = "debug", skip(self))]
+    fn set_fan_mode(&self, mode: FanMode) -> HwResult<()> {
+        write_attr(&self.hp_wmi_file("pwm1_enable")?, mode.pwm_enable())
+    }
+
+    fn platform_profile(&self) -> HwResult<String> {
+        read_attr(&self.root.sys("firmware/acpi/platform_profile"))
+    }
+
+    fn platform_profile_choices(&self) -> HwResult<Vec<String>> {
+        let choices = read_attr(&self.root.sys("firmware/acpi/platform_profile_choices"))?;
+
+        Ok(choices.split_whitespace().map(str::to_string).collect())
+    }
+
+    fn set_platform_profile(&self, profile: &str) -> HwResult<()> {
+        write_attr(&self.root.sys("firmware/acpi/platform_profile"), profile)
+    }
+
+    fn temp_sensors(&self) -> HwResult<Vec<TempSensor>> {
+        if !self.root.sys("class/hwmon").is_dir() {
+            return Err(HwError::missing("/sys/class/hwmon"));
+        }
+
+        Ok(hwmon::scan_temps(&self.root))
+    }
+
+    fn gpus(&self) -> HwResult<Vec<GpuStatus>> {
+        if self.gpus.is_empty() {
+            return Err(HwError::missing("gpu"));
+        }
+
+        Ok(self
+            .gpus
+            .iter()
+            .map(|gpu| gpu::status(&self.root, gpu.as_ref()))
+            .collect())
+    }
+
+    fn memory(&self) -> HwResult<MemInfo> {
+        let path = self.root.proc("meminfo");
+        let text = fs::read_to_string(&path).map_err(|err| HwError::from_io(err, &path))?;
+
+        MemInfo::parse(&text)
+    }
+
+    fn cpu_freqs(&self) -> HwResult<Vec<CoreFreq>> {
+        cpufreq::scan_freqs(&self.root)
+    }
+
+    fn cpu_policies(&self) -> HwResult<Vec<CpuPolicy>> {
+        cpufreq::scan_policies(&self.root)
+    }
+
+    fn set_governor(&self, policy: &str, governor: &str) -> HwResult<()> {
+        cpufreq::set_governor(&self.root, policy, governor)
+    }
+
+    fn set_epp(&self, policy: &str, epp: &str) -> HwResult<()> {
+        cpufreq::set_epp(&self.root, policy, epp)
+    }
+
+    fn rapl_zones(&self) -> HwResult<Vec<RaplZone>> {
+        rapl::scan_zones(&self.root)
+    }
+
+    fn cpu_boost(&self) -> HwResult<bool> {
+        cpufreq::boost(&self.root)
+    }
+
+    fn set_cpu_boost(&self, enabled: bool) -> HwResult<()> {
+        cpufreq::set_boost(&self.root, enabled)
+    }
+
+    fn power_supplies(&self) -> HwResult<PowerSupplies> {
+        power_supply::scan(&self.root)
+    }
+
+    fn set_charge_threshold(
+        &self,
+        battery: &str,
+        which: ChargeThreshold,
+        percent: u8,
+    ) -> HwResult<()> {
+        power_supply::set_threshold(&self.root, battery, which, percent)
+    }
+
+    fn cpu_usage(&self) -> HwResult<Vec<f32>> {
+        let stat = self.stat()?;
+        if stat.cores.is_empty() {
+            return Err(HwError::missing("cpu usage"));
+        }
+
+        // the first read has nothing to compare against, it shows 0%
+        let mut last = self.cpu_times.lock().unwrap();
+        let usage = stat
+            .cores
+            .iter()
+            .enumerate()
+            .map(|(idx, times)| {
+                let old = last.as_ref().and_then(|old| old.cores.get(idx));
+                old.map_or(0., |old| times.usage_since(old))
+            })
+            .collect();
+        *last = Some(stat);
+
+        Ok(usage)
+    }
+
+    fn processes(&self) -> HwResult<Vec<ProcessInfo>> {
+        let stat = self.stat()?;
+        let stats = process::scan(&self.root)?;
+
+        Ok(self.process_times.lock().unwrap().update(
+            stat.all,
+            stat.cores.len(),
+            stats,
+            self.page_size,
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fixture() -> SysfsBackend {
+        SysfsBackend::new(
+            SysRoot::new(concat!(env!("CARGO_MANIFEST_DIR"), "/fixtures/hp-wmi")),
+            false,
+        )
+    }
+
+    #[test]
+    fn cpu_usage_comes_from_the_root() {
+        let backend = fixture();
+
+        // the fixture never moves, so no core is ever busy
+        assert_eq!(backend.cpu_usage(), Ok(vec![0.; 4]));
+        assert_eq!(backend.cpu_usage(), Ok(vec![0.; 4]));
+    }
+
+    #[test]
+    fn processes_come_from_the_root() {
+        let backend = fixture();
+        let mut processes = backend.processes().unwrap();
+        processes.sort_by_key(|process| process.pid);
+
+        let names: Vec<&str> = processes.iter().map(|p| p.name.as_str()).collect();
+        assert_eq!(names, ["systemd", "tmux: server", "firefox"]);
+        assert_eq!(processes[1].memory, 1234 * process::page_size());
+    }
+}
@@ -0,0 +1,447 @@
+// Every metric's recent past, for the charts. Each metric keeps its last
+// RAW_CAPACITY samples as they were taken, plus min/avg/max buckets covering
+// the last hour and the last day. The sampler records into it, charts read
+// whichever window they're zoomed to.
+
+use std::{
+    collections::{HashMap, VecDeque},
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use crate::{hardware::cpufreq, sampler::Snapshot};
+
+// samples kept as they are, 2 minutes of the fastest sensors (200ms)
+pub const RAW_CAPACITY: usize = 600;
+// buckets per downsampled tier
+pub const BUCKETS: usize = 360;
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum Metric {
+    // ºC
+    CpuTemp,
+    GpuTemp,
+    // a hwmon sensor, by TempSensor id
+    Temp(String),
+    FanRpm(usize),
+    // GHz
+    CpuFreqAvg,
+    CpuFreqPeak,
+    // watts of a RAPL zone, by name
+    Power(String),
+    // GiB
+    MemoryUsed,
+}
+
+// How far back a chart looks.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Window {
+    #[default]
+    Minute,
+    Hour,
+    Day,
+}
+
+impl Window {
+    // Minute -> Hour -> Day -> Minute
+    pub fn next(self) -> Window {
+        match self {
+            Window::Minute => Window::Hour,
+            Window::Hour => Window::Day,
+            Window::Day => Window::Minute,
+        }
+    }
+
+    pub fn duration(self) -> Duration {
+        match self {
+            Window::Minute => Duration::from_secs(60),
+            Window::Hour => Duration::from_secs(60 * 60),
+            Window::Day => Duration::from_secs(24 * 60 * 60),
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Window::Minute => "1m",
+            Window::Hour => "1h",
+            Window::Day => "24h",
+        }
+    }
+
+    // BUCKETS of these cover the window
+    fn bucket(self) -> Duration {
+        self.duration() / BUCKETS as u32
+    }
+}
+
+// One point on a chart. x is in seconds relative to now, so <= 0. Raw
+// samples have min == avg == max.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Point {
+    pub x: f64,
+    pub min: f64,
+    pub avg: f64,
+    pub max: f64,
+}
+
+fn seconds_ago(at: Instant, now: Instant) -> f64 {
+    -now.saturating_duration_since(at).as_secs_f64()
+}
+
+// Fixed capacity, the oldest go first.
+struct Ring<T> {
+    items: VecDeque<T>,
+    capacity: usize,
+}
+
+impl<T> Ring<T> {
+    fn new(capacity: usize) -> Self {
+        Self {
+            items: VecDeque::with_capacity(capacity),
+            capacity,
+        }
+    }
+
+    fn push(&mut self, item: T) {
+        if self.items.len() == self.capacity {
+            self.items.pop_front();
+        }
+        self.items.push_back(item);
+    }
+}
+
+#[derive(Clone, Copy)]
+struct Bucket {
+    start: Instant,
+    min: f64,
+    max: f64,
+    sum: f64,
+    count: u32,
+}
+
+impl Bucket {
+    fn new(start: Instant, value: f64) -> Self {
+        Self {
+            start,
+            min: value,
+            max: value,
+            sum: value,
+            count: 1,
+        }
+    }
+
+    fn add(&mut self, value: f64) {
+        self.min = self.min.min(value);
+        self.max = self.max.max(value);
+        self.sum += value;
+        self.count += 1;
+    }
+
+    fn point(&self, now: Instant) -> Point {
+        Point {
+            x: seconds_ago(self.start, now),
+            min: self.min,
+            avg: self.sum / f64::from(self.count),
+            max: self.max,
+        }
+    }
+}
+
+// Samples folded into buckets of `width`, the newest one still filling up.
+struct Tier {
+    width: Duration,
+    closed: Ring<Bucket>,
+    open: Option<Bucket>,
+}
+
+impl Tier {
+    fn new(window: Window) -> Self {
+        Self {
+            width: window.bucket(),
+            closed: Ring::new(BUCKETS),
+            open: None,
+        }
+    }
+
+    fn push(&mut self, at: Instant, value: f64) {
+        match &mut self.open {
+            Some(bucket) if at < bucket.start + self.width => bucket.add(value),
+            _ => {
+                if let Some(full) = self.open.replace(Bucket::new(at, value)) {
+                    self.closed.push(full);
+                }
+            }
+        }
+    }
+
+    fn points(&self, now: Instant) -> Vec<Point> {
+        self.closed
+            .items
+            .iter()
+            .chain(self.open.as_ref())
+            .map(|bucket| bucket.point(now))
+            .collect()
+    }
+}
+
+struct Series {
+    raw: Ring<(Instant, f64)>,
+    hour: Tier,
+    day: Tier,
+}
+
+impl Series {
+    fn new() -> Self {
+        Self {
+            raw: Ring::new(RAW_CAPACITY),
+            hour: Tier::new(Window::Hour),
+            day: Tier::new(Window::Day),
+        }
+    }
+
+    fn last(&self) -> Option<Instant> {
+        self.raw.items.back().map(|(at, _)| *at)
+    }
+
+    fn push(&mut self, at: Instant, value: f64) {
+        self.raw.push((at, value));
+        self.hour.push(at, value);
+        self.day.push(at, value);
+    }
+
+    fn points(&self, window: Window, now: Instant) -> Vec<Point> {
+        let oldest = -window.duration().as_secs_f64();
+
+        let points = match window {
+            Window::Minute => self
+                .raw
+                .items
+                .iter()
+                .map(|&(at, value)| Point {
+                    x: seconds_ago(at, now),
+                    min: value,
+                    avg: value,
+                    max: value,
+                })
+                .collect(),
+            Window::Hour => self.hour.points(now),
+            Window::Day => self.day.points(now),
+        };
+
+        points
+            .into_iter()
+            .filter(|point| point.x >= oldest)
+            .collect()
+    }
+}
+
+#[derive(Default)]
+pub struct History {
+    series: HashMap<Metric, Series>,
+}
+
+impl History {
+    // adds a sample taken `at`, unless it's one we already have
+    pub fn push(&mut self, metric: Metric, at: Instant, value: f64) {
+        let series = self.series.entry(metric).or_insert_with(Series::new);
+        if series.last().is_some_and(|last| at <= last) {
+            return;
+        }
+        series.push(at, value);
+    }
+
+    // every metric in the snapshot, readers that failed are skipped
+    pub fn record(&mut self, snapshot: &Snapshot) {
+        if let Ok(temp) = snapshot.cpu_temp.value {
+            self.push(Metric::CpuTemp, snapshot.cpu_temp.taken, temp as f64);
+        }
+        // asleep cards have no temperature, rather a gap than a 0
+        if let Ok(Some(temp)) = snapshot.gpu_temp.value {
+            self.push(Metric::GpuTemp, snapshot.gpu_temp.taken, temp as f64);
+        }
+        if let Ok(sensors) = &snapshot.temp_sensors.value {
+            for sensor in sensors {
+                let metric = Metric::Temp(sensor.id.clone());
+                self.push(metric, snapshot.temp_sensors.taken, sensor.input);
+            }
+        }
+
+        // fans are read along with the fan list
+        for fan in &snapshot.fans {
+            if let Ok(speed) = fan.speed {
+                let metric = Metric::FanRpm(fan.id);
+                self.push(metric, snapshot.fan_list.taken, f64::from(speed));
+            }
+        }
+
+        if let Ok(freqs) = &snapshot.cpu_freq.value
+            && !freqs.is_empty()
+        {
+            let taken = snapshot.cpu_freq.taken;
+            self.push(Metric::CpuFreqAvg, taken, cpufreq::average(freqs));
+            self.push(Metric::CpuFreqPeak, taken, cpufreq::fastest(freqs));
+        }
+
+        // watts since the previous rapl read
+        for zone in &snapshot.cpu_power {
+            let metric = Metric::Power(zone.name.clone());
+            self.push(metric, snapshot.rapl.taken, zone.watts);
+        }
+
+        if let Ok(mem) = &snapshot.memory.value {
+            let gib = mem.used() as f64 / 1024. / 1024.;
+            self.push(Metric::MemoryUsed, snapshot.memory.taken, gib);
+        }
+    }
+
+    // what `metric` did over `window`, oldest first
+    pub fn points(&self, metric: &Metric, window: Window, now: Instant) -> Vec<Point> {
+        self.series
+            .get(metric)
+            .map(|series| series.points(window, now))
+            .unwrap_or_default()
+    }
+}
+
+// Shared between the sampler, which records, and the charts, which read.
+#[derive(Clone, Default)]
+pub struct HistoryHandle {
+    history: Arc<Mutex<History>>,
+}
+
+impl HistoryHandle {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn record(&self, snapshot: &Snapshot) {
+        self.history.lock().unwrap().record(snapshot);
+    }
+
+    // keep it short, the sampler waits on this
+    pub fn read<T>(&self, f: impl FnOnce(&History) -> T) -> T {
+        f(&self.history.lock().unwrap())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sampler::Sample;
+
+    fn secs(start: Instant, secs: f64) -> Instant {
+        start + Duration::from_secs_f64(secs)
+    }
+
+    #[test]
+    fn raw_samples_drop_the_oldest() {
+        let start = Instant::now();
+        let mut series = Series::new();
+        for idx in 0..RAW_CAPACITY + 5 {
+            series.push(secs(start, idx as f64 * 0.1), idx as f64);
+        }
+
+        assert_eq!(series.raw.items.len(), RAW_CAPACITY);
+        assert_eq!(series.raw.items.front().unwrap().1, 5.);
+    }
+
+    #[test]
+    fn buckets_fold_min_avg_max() {
+        let start = Instant::now();
+        let mut series = Series::new();
+        for (at, value) in [(0., 1.), (1., 5.), (2., 3.)] {
+            series.push(secs(start, at), value);
+        }
+        // hour buckets are 10s wide, this one starts the next
+        series.push(secs(start, 10.), 7.);
+
+        let now = secs(start, 10.);
+        let hour = series.points(Window::Hour, now);
+        assert_eq!(
+            hour,
+            [
+                Point {
+                    x: -10.,
+                    min: 1.,
+                    avg: 3.,
+                    max: 5.
+                },
+                Point {
+                    x: 0.,
+                    min: 7.,
+                    avg: 7.,
+                    max: 7.
+                },
+            ]
+        );
+
+        // day buckets are 4 minutes wide, still the first one
+        let day = series.points(Window::Day, now);
+        assert_eq!(day.len(), 1);
+        assert_eq!((day[0].min, day[0].avg, day[0].max), (1., 4., 7.));
+
+        series.push(secs(start, 240.), 2.);
+        let day = series.points(Window::Day, secs(start, 240.));
+        assert_eq!(day.len(), 2);
+        assert_eq!(day[1].avg, 2.);
+        assert_eq!(series.day.closed.items.len(), 1);
+    }
+
+    #[test]
+    fn windows_only_reach_back_as_far_as_they_go() {
+        let start = Instant::now();
+        let mut history = History::default();
+        // every 30s for 25 hours
+        let samples = 25 * 60 * 2;
+        for idx in 0..=samples {
+            history.push(Metric::CpuTemp, secs(start, idx as f64 * 30.), 50.);
+        }
+        let now = secs(start, samples as f64 * 30.);
+
+        for window in [Window::Minute, Window::Hour, Window::Day] {
+            let points = history.points(&Metric::CpuTemp, window, now);
+            let oldest = -window.duration().as_secs_f64();
+            assert!(
+                points
+                    .iter()
+                    .all(|point| point.x >= oldest && point.x <= 0.)
+            );
+            assert_eq!(points.last().unwrap().x, 0.);
+
+            match window {
+                // 0, -30 and -60
+                Window::Minute => assert_eq!(points.len(), 3),
+                // one sample per 10s bucket
+                Window::Hour => assert_eq!(points.len(), 121),
+                // a full ring of 4 minute buckets plus the open one
+                Window::Day => assert_eq!(points.len(), BUCKETS + 1),
+            }
+        }
+    }
+
+    #[test]
+    fn a_snapshot_is_recorded_once() {
+        let mut history = History::default();
+        let snapshot = Snapshot {
+            cpu_temp: Sample::new(Ok(55)),
+            ..Snapshot::default()
+        };
+
+        history.record(&snapshot);
+        history.record(&snapshot.clone());
+        let now = Instant::now();
+        assert_eq!(
+            history.points(&Metric::CpuTemp, Window::Minute, now).len(),
+            1
+        );
+
+        let newer = Snapshot {
+            cpu_temp: Sample::new(Ok(57)),
+            ..snapshot
+        };
+        history.record(&newer);
+        let points = history.points(&Metric::CpuTemp, Window::Minute, Instant::now());
+        assert_eq!(points.len(), 2);
+        assert_eq!(points[1].avg, 57.);
+    }
+}
@@ -0,0 +1,220 @@
+// Appends every entry to a text file, rotated by size:
+// my-template.log -> my-template.log.1 -> ... -> my-template.log.{KEEP}
+
+use std::{
+    env,
+    fs::{self, File, OpenOptions},
+    io::{self, Write},
+    path::{Path, PathBuf},
+};
+
+use crate::logs::{LogEntry, LogSink};
+
+// rotate once the live file would grow past this
+pub const MAX_BYTES: u64 = 1024 * 1024;
+// rotated files kept besides the live one
+pub const KEEP: usize = 3;
+
+// one line per entry, continuation lines of multi-line messages indented
+pub fn format_line(entry: &LogEntry) -> String {
+    format!(
+        "{} {:<5} {:<9} {}\n",
+        entry.timestamp(),
+        entry.level,
+        entry.source,
+        entry.message.replace('\n', "\n    ")
+    )
+}
+
+// $XDG_STATE_HOME/my-template/my-template.log, $XDG_STATE_HOME defaulting to
+// ~/.local/state
+pub fn default_path() -> Option<PathBuf> {
+    let state = env::var_os("XDG_STATE_HOME")
+        .map(PathBuf::from)
+        .filter(|path| path.is_absolute())
+        .or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join(".local/state")))?;
+
+    let name = env!("CARGO_PKG_NAME");
+    Some(state.join(name).join(format!("{}.log", name)))
+}
+
+fn rotated(path: &Path, n: usize) -> PathBuf {
+    let mut name = path.as_os_str().to_owned();
+    name.push(format!(".{}", n));
+    PathBuf::from(name)
+}
+
+pub struct FileSink {
+    path: PathBuf,
+    file: File,
+    // bytes in the live file
+    size: u64,
+    max_bytes: u64,
+    keep: usize,
+}
+
+impl FileSink {
+    pub fn open(path: PathBuf) -> io::Result<Self> {
+        Self::with_limits(path, MAX_BYTES, KEEP)
+    }
+
+    pub fn with_limits(path: PathBuf, max_bytes: u64, keep: usize) -> io::Result<Self> {
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let file = OpenOptions::new().create(true).append(true).open(&path)?;
+        let size = file.metadata()?.len();
+
+        Ok(Self {
+            path,
+            file,
+            size,
+            max_bytes,
+            keep,
+        })
+    }
+
+    // shifts every file up by one, the oldest falls off
+    fn rotate(&mut self) -> io::Result<()> {
+        if self.keep == 0 {
+            self.file.set_len(0)?;
+            self.size = 0;
+            return Ok(());
+        }
+
+        for n in (1..self.keep).rev() {
+            let from = rotated(&self.path, n);
+            if from.exists() {
+                fs::rename(&from, rotated(&self.path, n + 1))?;
+            }
+        }
+        fs::rename(&self.path, rotated(&self.path, 1))?;
+
+        self.file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)?;
+        self.size = 0;
+        Ok(())
+    }
+}
+
+impl LogSink for FileSink {
+    fn name(&self) -> String {
+        format!("log file {}", self.path.display())
+    }
+
+    fn write(&mut self, entry: &LogEntry) -> io::Result<()> {
+        let line = format_line(entry);
+        let len = line.len() as u64;
+
+        // an empty file takes the line even when it's too long on its own
+        if self.size > 0 && self.size + len > self.max_bytes {
+            self.rotate()?;
+        }
+
+        self.file.write_all(line.as_bytes())?;
+        self.size += len;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{process, time::SystemTime};
+
+    use super::*;
+    use crate::logs::Level;
+
+    fn entry(message: &str) -> LogEntry {
+        LogEntry {
+            time: SystemTime::now(),
+            level: Level::Info,
+            source: "logs",
+            message: message.to_string(),
+        }
+    }
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = env::temp_dir().join(format!("my-template-{}-{}", name, process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        dir
+    }
+
+    #[test]
+    fn indents_continuation_lines() {
+        let line = format_line(&entry("first\nsecond"));
+        assert!(line.ends_with(" INFO  logs      first\n    second\n"));
+    }
+
+    #[test]
+    fn rotates_at_the_size_limit() {
+        let dir = temp_dir("rotate");
+        let path = dir.join("test.log");
+        let line_len = format_line(&entry("0")).len() as u64;
+
+        // room for two lines per file
+        let mut sink = FileSink::with_limits(path.clone(), line_len * 2, 2).unwrap();
+        for n in 0..7 {
+            sink.write(&entry(&n.to_string())).unwrap();
+        }
+
+        let read = |path: &Path| {
+            fs::read_to_string(path)
+                .unwrap()
+                .lines()
+                .map(|line| line.rsplit(' ').next().unwrap().to_string())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(read(&path), ["6"]);
+        assert_eq!(read(&rotated(&path, 1)), ["4", "5"]);
+        assert_eq!(read(&rotated(&path, 2)), ["2", "3"]);
+        // 0 and 1 fell off
+        assert!(!rotated(&path, 3).exists());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn picks_up_the_size_of_an_existing_file() {
+        let dir = temp_dir("reopen");
+        let path = dir.join("test.log");
+        let line_len = format_line(&entry("0")).len() as u64;
+
+        FileSink::with_limits(path.clone(), line_len * 2, 1)
+            .unwrap()
+            .write(&entry("0"))
+            .unwrap();
+
+        let mut sink = FileSink::with_limits(path.clone(), line_len * 2, 1).unwrap();
+        sink.write(&entry("1")).unwrap();
+        sink.write(&entry("2")).unwrap();
+
+        assert_eq!(
+            fs::read_to_string(rotated(&path, 1))
+                .unwrap()
+                .lines()
+                .count(),
+            2
+        );
+        assert_eq!(fs::read_to_string(&path).unwrap().lines().count(), 1);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn keep_zero_truncates_in_place() {
+        let dir = temp_dir("truncate");
+        let path = dir.join("test.log");
+        let line_len = format_line(&entry("0")).len() as u64;
+
+        let mut sink = FileSink::with_limits(path.clone(), line_len, 0).unwrap();
+        sink.write(&entry("0")).unwrap();
+        sink.write(&entry("1")).unwrap();
+
+        assert!(fs::read_to_string(&path).unwrap().ends_with(" 1\n"));
+        assert!(!rotated(&path, 1).exists());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
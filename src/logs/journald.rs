@@ -0,0 +1,150 @@
+// Sends every entry to the systemd journal over its native protocol: one
+// datagram per entry on /run/systemd/journal/socket, holding KEY=value
+// fields. See systemd's "Native Journal Protocol" documentation.
+
+use std::{
+    io,
+    os::unix::net::UnixDatagram,
+    path::{Path, PathBuf},
+};
+
+use crate::logs::{Level, LogEntry, LogSink};
+
+pub const SOCKET: &str = "/run/systemd/journal/socket";
+
+// syslog priorities
+fn priority(level: Level) -> u8 {
+    match level {
+        Level::Debug => 7,
+        Level::Info => 6,
+        Level::Warn => 4,
+        Level::Error => 3,
+    }
+}
+
+fn push_field(datagram: &mut Vec<u8>, key: &str, value: &str) {
+    datagram.extend_from_slice(key.as_bytes());
+
+    // values with a newline go length-prefixed: KEY\n<u64 le length><value>\n
+    if value.contains('\n') {
+        datagram.push(b'\n');
+        datagram.extend_from_slice(&(value.len() as u64).to_le_bytes());
+    } else {
+        datagram.push(b'=');
+    }
+
+    datagram.extend_from_slice(value.as_bytes());
+    datagram.push(b'\n');
+}
+
+// the datagram for one entry, the source goes in LOG_SOURCE so
+// `journalctl LOG_SOURCE=fans` works
+pub fn encode(entry: &LogEntry, identifier: &str) -> Vec<u8> {
+    let mut datagram = Vec::new();
+    push_field(
+        &mut datagram,
+        "PRIORITY",
+        &priority(entry.level).to_string(),
+    );
+    push_field(&mut datagram, "SYSLOG_IDENTIFIER", identifier);
+    push_field(&mut datagram, "LOG_SOURCE", entry.source);
+    push_field(&mut datagram, "MESSAGE", &entry.message);
+    datagram
+}
+
+pub struct JournaldSink {
+    socket: UnixDatagram,
+    path: PathBuf,
+}
+
+impl JournaldSink {
+    // `path` is SOCKET, or a stand-in socket to test against
+    pub fn connect(path: &Path) -> io::Result<Self> {
+        let socket = UnixDatagram::unbound()?;
+        socket.connect(path)?;
+
+        Ok(Self {
+            socket,
+            path: path.to_path_buf(),
+        })
+    }
+}
+
+impl LogSink for JournaldSink {
+    fn name(&self) -> String {
+        format!("journald socket {}", self.path.display())
+    }
+
+    // entries are far below the datagram size limit, so no memfd fallback
+    fn write(&mut self, entry: &LogEntry) -> io::Result<()> {
+        self.socket
+            .send(&encode(entry, env!("CARGO_PKG_NAME")))
+            .map(|_| ())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{env, fs, process, time::SystemTime};
+
+    use super::*;
+
+    fn entry(level: Level, message: &str) -> LogEntry {
+        LogEntry {
+            time: SystemTime::now(),
+            level,
+            source: "fans",
+            message: message.to_string(),
+        }
+    }
+
+    #[test]
+    fn encodes_single_line_fields() {
+        let datagram = encode(&entry(Level::Warn, "fan 1: target 3000 rpm"), "test");
+
+        assert_eq!(
+            datagram,
+            b"PRIORITY=4\nSYSLOG_IDENTIFIER=test\nLOG_SOURCE=fans\nMESSAGE=fan 1: target 3000 rpm\n"
+        );
+    }
+
+    #[test]
+    fn length_prefixes_multi_line_values() {
+        let message = "first\nsecond";
+        let datagram = encode(&entry(Level::Error, message), "test");
+
+        let mut expected =
+            b"PRIORITY=3\nSYSLOG_IDENTIFIER=test\nLOG_SOURCE=fans\nMESSAGE\n".to_vec();
+        expected.extend_from_slice(&(message.len() as u64).to_le_bytes());
+        expected.extend_from_slice(b"first\nsecond\n");
+        assert_eq!(datagram, expected);
+    }
+
+    #[test]
+    fn sends_one_datagram_per_entry() {
+        let dir = env::temp_dir().join(format!("my-template-journald-{}", process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("socket");
+        let _ = fs::remove_file(&path);
+
+        // stands in for /run/systemd/journal/socket
+        let journal = UnixDatagram::bind(&path).unwrap();
+        let mut sink = JournaldSink::connect(&path).unwrap();
+
+        let first = entry(Level::Info, "one");
+        let second = entry(Level::Debug, "two\nlines");
+        sink.write(&first).unwrap();
+        sink.write(&second).unwrap();
+
+        let mut buf = [0; 1024];
+        for sent in [first, second] {
+            let len = journal.recv(&mut buf).unwrap();
+            assert_eq!(&buf[..len], encode(&sent, env!("CARGO_PKG_NAME")));
+        }
+
+        // the journal going away is an error for the pipeline to drop us on
+        drop(journal);
+        fs::remove_dir_all(&dir).unwrap();
+        assert!(sink.write(&entry(Level::Info, "gone")).is_err());
+    }
+}
@@ -0,0 +1,206 @@
+// A tracing layer that turns events into log entries, so library-level
+// diagnostics end up in the Logs panel (and the sinks) next to everything
+// else. Events are prefixed with the spans they happened in:
+//
+//     set_fan_target{fan=1 rpm=3000}: write path=/sys/... value=3000
+
+use std::fmt::{self, Write};
+
+use tracing::{
+    Event, Subscriber,
+    field::{Field, Visit},
+    span,
+};
+use tracing_subscriber::{Layer, layer::Context, registry::LookupSpan};
+
+use crate::logs::{Level, LogHandle};
+
+fn level(level: tracing::Level) -> Level {
+    match level {
+        tracing::Level::ERROR => Level::Error,
+        tracing::Level::WARN => Level::Warn,
+        tracing::Level::INFO => Level::Info,
+        // no trace level of our own
+        _ => Level::Debug,
+    }
+}
+
+// the subsystem, from the module path the event came from
+pub fn source(target: &'static str) -> &'static str {
+    match target.rsplit("::").next().unwrap_or(target) {
+        "gpu" | "nvidia" => "gpu",
+        "fans_widget" | "fan_curve" => "fans",
+        "sysfs" | "attr" => "hardware",
+        other => other,
+    }
+}
+
+// the message bare and first, every other field as key=value
+#[derive(Default)]
+struct Fields {
+    message: String,
+    rest: String,
+}
+
+impl Fields {
+    fn joined(self) -> String {
+        let mut joined = self.message;
+        joined.push_str(&self.rest);
+        joined.trim_start().to_string()
+    }
+}
+
+impl Visit for Fields {
+    fn record_str(&mut self, field: &Field, value: &str) {
+        match field.name() {
+            "message" => self.message.push_str(value),
+            name => {
+                let _ = write!(self.rest, " {}={}", name, value);
+            }
+        }
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+        let _ = match field.name() {
+            "message" => write!(self.message, "{:?}", value),
+            name => write!(self.rest, " {}={:?}", name, value),
+        };
+    }
+}
+
+// a span's fields, formatted once when it's created
+struct SpanFields(String);
+
+pub struct LogsLayer {
+    logs: LogHandle,
+}
+
+impl LogsLayer {
+    pub fn new(logs: LogHandle) -> Self {
+        Self { logs }
+    }
+}
+
+impl<S> Layer<S> for LogsLayer
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+{
+    fn on_new_span(&self, attrs: &span::Attributes<'_>, id: &span::Id, ctx: Context<'_, S>) {
+        let Some(span) = ctx.span(id) else {
+            return;
+        };
+
+        let mut fields = Fields::default();
+        attrs.record(&mut fields);
+        span.extensions_mut().insert(SpanFields(fields.joined()));
+    }
+
+    fn on_record(&self, id: &span::Id, values: &span::Record<'_>, ctx: Context<'_, S>) {
+        let Some(span) = ctx.span(id) else {
+            return;
+        };
+
+        let mut fields = Fields::default();
+        values.record(&mut fields);
+        let recorded = fields.joined();
+
+        let mut extensions = span.extensions_mut();
+        if let Some(SpanFields(existing)) = extensions.get_mut::<SpanFields>() {
+            if !existing.is_empty() {
+                existing.push(' ');
+            }
+            existing.push_str(&recorded);
+        }
+    }
+
+    fn on_event(&self, event: &Event<'_>, ctx: Context<'_, S>) {
+        let mut message = String::new();
+
+        if let Some(scope) = ctx.event_scope(event) {
+            for span in scope.from_root() {
+                message.push_str(span.name());
+                if let Some(SpanFields(fields)) = span.extensions().get::<SpanFields>()
+                    && !fields.is_empty()
+                {
+                    let _ = write!(message, "{{{}}}", fields);
+                }
+                message.push_str(": ");
+            }
+        }
+
+        let mut fields = Fields::default();
+        event.record(&mut fields);
+        message.push_str(&fields.joined());
+
+        let metadata = event.metadata();
+        self.logs
+            .log(level(*metadata.level()), source(metadata.target()), message);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tracing::{debug_span, info_span, trace, warn};
+    use tracing_subscriber::{Registry, layer::SubscriberExt};
+
+    use super::*;
+
+    fn capture(f: impl FnOnce()) -> Vec<(Level, &'static str, String)> {
+        let logs = LogHandle::new();
+        let subscriber = Registry::default().with(LogsLayer::new(logs.clone()));
+        tracing::subscriber::with_default(subscriber, f);
+
+        logs.read(|buffer| {
+            buffer
+                .entries()
+                .iter()
+                .map(|entry| (entry.level, entry.source, entry.message.clone()))
+                .collect()
+        })
+    }
+
+    #[test]
+    fn sources_come_from_the_module() {
+        assert_eq!(source("my_template::hardware::nvidia"), "gpu");
+        assert_eq!(source("my_template::widgets::fans_widget"), "fans");
+        assert_eq!(source("my_template::hardware::attr"), "hardware");
+        assert_eq!(source("my_template::sampler"), "sampler");
+        assert_eq!(source("sampler"), "sampler");
+    }
+
+    #[test]
+    fn events_carry_their_span_chain() {
+        let entries = capture(|| {
+            let outer = info_span!("set_fan_target", fan = 1, rpm = tracing::field::Empty);
+            let _outer = outer.enter();
+            outer.record("rpm", 3000);
+
+            let _inner = debug_span!("write").entered();
+            warn!(
+                target: "my_template::hardware::sysfs",
+                path = "/sys/class/hwmon/hwmon3/fan1_target",
+                "write failed: {}",
+                "EINVAL"
+            );
+            trace!(target: "my_template::hardware::gpu", "done");
+        });
+
+        assert_eq!(
+            entries,
+            [
+                (
+                    Level::Warn,
+                    "hardware",
+                    "set_fan_target{fan=1 rpm=3000}: write: write failed: EINVAL \
+                     path=/sys/class/hwmon/hwmon3/fan1_target"
+                        .to_string()
+                ),
+                (
+                    Level::Debug,
+                    "gpu",
+                    "set_fan_target{fan=1 rpm=3000}: write: done".to_string()
+                ),
+            ]
+        );
+    }
+}
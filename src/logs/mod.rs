@@ -0,0 +1,440 @@
+// The log pipeline. Anything can log through a cloneable LogHandle; entries
+// land in a bounded ring buffer that the Logs panel draws from, and in every
+// sink added to it (log file, journald). Sinks are written from their own
+// thread, so slow IO never holds up a log call or a reader.
+
+use std::{
+    collections::VecDeque,
+    fmt, io,
+    sync::{
+        Arc, Mutex,
+        mpsc::{self, Receiver, Sender},
+    },
+    thread,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+pub mod file;
+pub mod journald;
+pub mod layer;
+
+// entries kept in memory, the oldest go first
+pub const CAPACITY: usize = 2000;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Level {
+    Debug,
+    Info,
+    Warn,
+    Error,
+}
+
+impl Level {
+    // Debug -> Info -> Warn -> Error -> Debug
+    pub fn next(self) -> Level {
+        match self {
+            Level::Debug => Level::Info,
+            Level::Info => Level::Warn,
+            Level::Warn => Level::Error,
+            Level::Error => Level::Debug,
+        }
+    }
+}
+
+impl fmt::Display for Level {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // pad, not write!, so the widget can line the levels up
+        f.pad(match self {
+            Level::Debug => "DEBUG",
+            Level::Info => "INFO",
+            Level::Warn => "WARN",
+            Level::Error => "ERROR",
+        })
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct LogEntry {
+    pub time: SystemTime,
+    pub level: Level,
+    // subsystem, "fans", "gpu", "power", ...
+    pub source: &'static str,
+    pub message: String,
+}
+
+impl LogEntry {
+    fn local_time(&self) -> Option<libc::tm> {
+        let secs = self
+            .time
+            .duration_since(UNIX_EPOCH)
+            .map(|elapsed| elapsed.as_secs())
+            .unwrap_or(0) as libc::time_t;
+
+        // SAFETY: localtime_r only writes to the tm we hand it
+        let mut tm: libc::tm = unsafe { std::mem::zeroed() };
+        if unsafe { libc::localtime_r(&secs, &mut tm) }.is_null() {
+            return None;
+        }
+        Some(tm)
+    }
+
+    // local wall clock "HH:MM:SS"
+    pub fn clock(&self) -> String {
+        match self.local_time() {
+            Some(tm) => format!("{:02}:{:02}:{:02}", tm.tm_hour, tm.tm_min, tm.tm_sec),
+            None => "--:--:--".to_string(),
+        }
+    }
+
+    // local "YYYY-MM-DD HH:MM:SS", for logs that outlive the day
+    pub fn timestamp(&self) -> String {
+        match self.local_time() {
+            Some(tm) => format!(
+                "{}-{:02}-{:02} {}",
+                tm.tm_year + 1900,
+                tm.tm_mon + 1,
+                tm.tm_mday,
+                self.clock()
+            ),
+            None => "----------- --:--:--".to_string(),
+        }
+    }
+}
+
+// Somewhere entries go besides the ring buffer.
+pub trait LogSink: Send {
+    // for the error logged when it stops working
+    fn name(&self) -> String;
+
+    fn write(&mut self, entry: &LogEntry) -> io::Result<()>;
+}
+
+// Which entries a reader wants to see.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct LogFilter {
+    // this level and above
+    pub min_level: Option<Level>,
+    pub source: Option<&'static str>,
+}
+
+impl LogFilter {
+    pub fn matches(&self, entry: &LogEntry) -> bool {
+        self.min_level.is_none_or(|level| entry.level >= level)
+            && self.source.is_none_or(|source| entry.source == source)
+    }
+}
+
+// byte ranges of `query` in `text`, ignoring ascii case
+pub fn find_matches(text: &str, query: &str) -> Vec<std::ops::Range<usize>> {
+    if query.is_empty() {
+        return Vec::new();
+    }
+
+    // ascii lowercasing keeps byte offsets the same
+    let text = text.to_ascii_lowercase();
+    let query = query.to_ascii_lowercase();
+
+    let mut matches = Vec::new();
+    let mut from = 0;
+    while let Some(idx) = text[from..].find(&query) {
+        let start = from + idx;
+        matches.push(start..start + query.len());
+        from = start + query.len();
+    }
+    matches
+}
+
+#[derive(Default)]
+pub struct LogBuffer {
+    entries: VecDeque<LogEntry>,
+    // how many entries fell off the front so far, so readers can keep
+    // absolute positions while the buffer turns over
+    dropped: u64,
+}
+
+impl LogBuffer {
+    pub fn entries(&self) -> &VecDeque<LogEntry> {
+        &self.entries
+    }
+
+    pub fn dropped(&self) -> u64 {
+        self.dropped
+    }
+
+    // entries with their absolute position
+    pub fn positioned(&self) -> impl Iterator<Item = (u64, &LogEntry)> {
+        (self.dropped..).zip(self.entries.iter())
+    }
+
+    // every source logged so far, sorted
+    pub fn sources(&self) -> Vec<&'static str> {
+        let mut sources: Vec<&'static str> = self.entries.iter().map(|e| e.source).collect();
+        sources.sort_unstable();
+        sources.dedup();
+        sources
+    }
+
+    fn push(&mut self, entry: LogEntry) {
+        if self.entries.len() == CAPACITY {
+            self.entries.pop_front();
+            self.dropped += 1;
+        }
+        self.entries.push_back(entry);
+    }
+}
+
+enum SinkMessage {
+    Add(Box<dyn LogSink>),
+    Entry(LogEntry),
+    // answered once everything sent before it is written
+    Flush(Sender<()>),
+}
+
+// a sink that fails once is dropped and the error logged to the rest, so
+// this can't loop
+fn write_sinks(sinks: &mut Vec<Box<dyn LogSink>>, entry: &LogEntry, buffer: &Mutex<LogBuffer>) {
+    let mut failed = Vec::new();
+    sinks.retain_mut(|sink| match sink.write(entry) {
+        Ok(()) => true,
+        Err(err) => {
+            failed.push(format!("{}: {}, no longer writing to it", sink.name(), err));
+            false
+        }
+    });
+
+    for message in failed {
+        let entry = LogEntry {
+            time: SystemTime::now(),
+            level: Level::Error,
+            source: "logs",
+            message,
+        };
+        buffer.lock().unwrap().push(entry.clone());
+        write_sinks(sinks, &entry, buffer);
+    }
+}
+
+fn run_sinks(rx: Receiver<SinkMessage>, buffer: Arc<Mutex<LogBuffer>>) {
+    let mut sinks = Vec::new();
+
+    for message in rx {
+        match message {
+            SinkMessage::Add(sink) => sinks.push(sink),
+            SinkMessage::Entry(entry) => write_sinks(&mut sinks, &entry, &buffer),
+            SinkMessage::Flush(done) => {
+                let _ = done.send(());
+            }
+        }
+    }
+}
+
+#[derive(Clone, Default)]
+pub struct LogHandle {
+    buffer: Arc<Mutex<LogBuffer>>,
+    // to the sink thread, started with the first sink
+    sinks: Arc<Mutex<Option<Sender<SinkMessage>>>>,
+}
+
+impl LogHandle {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // every entry from now on also goes to `sink`
+    pub fn add_sink(&self, sink: impl LogSink + 'static) {
+        let mut sinks = self.sinks.lock().unwrap();
+
+        if sinks.is_none() {
+            let (tx, rx) = mpsc::channel();
+            let buffer = self.buffer.clone();
+            let spawned = thread::Builder::new()
+                .name("log sinks".to_string())
+                .spawn(move || run_sinks(rx, buffer));
+
+            if let Err(err) = spawned {
+                drop(sinks);
+                self.error(
+                    "logs",
+                    format!("{}: no thread to write from: {}", sink.name(), err),
+                );
+                return;
+            }
+            *sinks = Some(tx);
+        }
+
+        if let Some(tx) = &*sinks {
+            let _ = tx.send(SinkMessage::Add(Box::new(sink)));
+        }
+    }
+
+    pub fn log(&self, level: Level, source: &'static str, message: impl Into<String>) {
+        let entry = LogEntry {
+            time: SystemTime::now(),
+            level,
+            source,
+            message: message.into(),
+        };
+
+        // sent while the buffer is locked, so sinks see the buffer's order
+        let mut buffer = self.buffer.lock().unwrap();
+        if let Some(tx) = &*self.sinks.lock().unwrap() {
+            let _ = tx.send(SinkMessage::Entry(entry.clone()));
+        }
+        buffer.push(entry);
+    }
+
+    // waits for the sinks to write everything logged so far, before exiting
+    pub fn flush(&self) {
+        let (done, wait) = mpsc::channel();
+        let sent = match &*self.sinks.lock().unwrap() {
+            Some(tx) => tx.send(SinkMessage::Flush(done)).is_ok(),
+            None => false,
+        };
+
+        if sent {
+            let _ = wait.recv();
+        }
+    }
+
+    pub fn debug(&self, source: &'static str, message: impl Into<String>) {
+        self.log(Level::Debug, source, message);
+    }
+
+    pub fn info(&self, source: &'static str, message: impl Into<String>) {
+        self.log(Level::Info, source, message);
+    }
+
+    pub fn warn(&self, source: &'static str, message: impl Into<String>) {
+        self.log(Level::Warn, source, message);
+    }
+
+    pub fn error(&self, source: &'static str, message: impl Into<String>) {
+        self.log(Level::Error, source, message);
+    }
+
+    // for readers, keep it short: every log call waits on this
+    pub fn read<T>(&self, f: impl FnOnce(&LogBuffer) -> T) -> T {
+        f(&self.buffer.lock().unwrap())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // keeps what it's given, or fails every write
+    struct TestSink {
+        written: Arc<Mutex<Vec<String>>>,
+        fail: bool,
+    }
+
+    impl LogSink for TestSink {
+        fn name(&self) -> String {
+            "test sink".to_string()
+        }
+
+        fn write(&mut self, entry: &LogEntry) -> io::Result<()> {
+            if self.fail {
+                return Err(io::Error::other("disk full"));
+            }
+            self.written.lock().unwrap().push(entry.message.clone());
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn matches_ignore_case() {
+        assert_eq!(find_matches("Fan 1: FAN target", "fan"), [0..3, 7..10]);
+        assert_eq!(find_matches("gpu suspended", "GPU S"), vec![0..5]);
+        assert!(find_matches("fans", "gpu").is_empty());
+    }
+
+    #[test]
+    fn matches_dont_overlap() {
+        assert_eq!(find_matches("aaaa", "aa"), [0..2, 2..4]);
+        assert_eq!(find_matches("aaa", "aa"), vec![0..2]);
+    }
+
+    #[test]
+    fn an_empty_query_matches_nothing() {
+        assert!(find_matches("anything", "").is_empty());
+        assert!(find_matches("", "").is_empty());
+    }
+
+    #[test]
+    fn positions_survive_the_buffer_turning_over() {
+        let logs = LogHandle::new();
+        for idx in 0..CAPACITY + 5 {
+            logs.info("app", format!("entry {}", idx));
+        }
+
+        logs.read(|buffer| {
+            assert_eq!(buffer.entries().len(), CAPACITY);
+            assert_eq!(buffer.dropped(), 5);
+
+            let (first, entry) = buffer.positioned().next().unwrap();
+            assert_eq!((first, entry.message.as_str()), (5, "entry 5"));
+            assert!(
+                buffer
+                    .positioned()
+                    .all(|(pos, entry)| entry.message == format!("entry {}", pos))
+            );
+        });
+    }
+
+    #[test]
+    fn failing_sinks_are_dropped_and_logged() {
+        let logs = LogHandle::new();
+        let written = Arc::new(Mutex::new(Vec::new()));
+        logs.info("app", "before any sink");
+        logs.add_sink(TestSink {
+            written: written.clone(),
+            fail: false,
+        });
+        logs.add_sink(TestSink {
+            written: Arc::new(Mutex::new(Vec::new())),
+            fail: true,
+        });
+
+        logs.info("app", "one");
+        logs.info("app", "two");
+        logs.flush();
+
+        let error = "test sink: disk full, no longer writing to it";
+        assert_eq!(*written.lock().unwrap(), ["one", error, "two"]);
+        // the sink thread logs the error, so it can land after "two"
+        let messages: Vec<String> =
+            logs.read(|buffer| buffer.entries().iter().map(|e| e.message.clone()).collect());
+        assert_eq!(messages.len(), 4);
+        assert_eq!(messages[..2], ["before any sink", "one"]);
+        assert!(messages.iter().any(|message| message == error));
+    }
+
+    #[test]
+    fn slow_sinks_dont_hold_up_readers() {
+        struct Stuck(Receiver<()>);
+
+        impl LogSink for Stuck {
+            fn name(&self) -> String {
+                "stuck".to_string()
+            }
+
+            fn write(&mut self, _: &LogEntry) -> io::Result<()> {
+                let _ = self.0.recv();
+                Ok(())
+            }
+        }
+
+        let logs = LogHandle::new();
+        let (release, stuck) = mpsc::channel();
+        logs.add_sink(Stuck(stuck));
+
+        logs.warn("app", "first");
+        logs.warn("app", "second");
+        assert_eq!(logs.read(|buffer| buffer.entries().len()), 2);
+
+        release.send(()).unwrap();
+        release.send(()).unwrap();
+        logs.flush();
+    }
+}
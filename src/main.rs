@@ -1,9 +1,37 @@
-use std::io;
+use std::{io, panic, sync::Arc};
+
+use crossterm::{
+    event::{DisableMouseCapture, EnableMouseCapture},
+    execute,
+};
+use tracing_subscriber::{filter::Targets, prelude::*};
 
 mod app;
+mod cli;
+mod fan_curve;
+mod hardware;
+mod history;
+mod logs;
+mod sampler;
 mod widgets;
 
-use crate::app::App;
+use crate::{
+    app::App,
+    cli::Args,
+    hardware::{
+        Backend,
+        error::HwError,
+        mock::{MockBackend, READERS},
+        root::SysRoot,
+        sysfs::SysfsBackend,
+    },
+    logs::{
+        LogHandle,
+        file::{self, FileSink},
+        journald::JournaldSink,
+        layer::LogsLayer,
+    },
+};
 
 #[macro_export]
 macro_rules! margin {
@@ -15,9 +43,92 @@ macro_rules! margin {
     };
 }
 
-fn main() -> io::Result<()> {
+// the log file and journald, failures only end up in the Logs panel
+fn open_sinks(args: &Args, logs: &LogHandle) {
+    let path = match &args.log_file {
+        _ if args.no_log_file => None,
+        Some(path) => Some(path.clone()),
+        None => file::default_path(),
+    };
+    if let Some(path) = path {
+        match FileSink::open(path.clone()) {
+            Ok(sink) => {
+                logs.add_sink(sink);
+                logs.info("logs", format!("writing to {}", path.display()));
+            }
+            Err(err) => logs.error("logs", format!("can't open {}: {}", path.display(), err)),
+        }
+    }
+
+    if let Some(socket) = &args.journald {
+        match JournaldSink::connect(socket) {
+            Ok(sink) => logs.add_sink(sink),
+            Err(err) => logs.error(
+                "logs",
+                format!("can't connect to journald at {}: {}", socket.display(), err),
+            ),
+        }
+    }
+}
+
+// our own events from debug up, everyone else's warnings, all into `logs`
+fn init_tracing(logs: &LogHandle) {
+    let targets = Targets::new()
+        .with_target(env!("CARGO_CRATE_NAME"), tracing::Level::DEBUG)
+        .with_default(tracing::Level::WARN);
+
+    tracing_subscriber::registry()
+        .with(LogsLayer::new(logs.clone()).with_filter(targets))
+        .init();
+}
+
+fn main() -> color_eyre::Result<()> {
+    // ratatui::init() wraps this hook, so a panic restores the terminal
+    // before the report is printed
+    color_eyre::install()?;
+
+    let args = Args::parse();
+    let root = SysRoot::resolve(args.root.clone());
+
+    // before the backend, so hardware discovery is logged too
+    let logs = LogHandle::new();
+    open_sinks(&args, &logs);
+    init_tracing(&logs);
+
+    let backend: Backend = if args.mock {
+        let mut mock = MockBackend::demo();
+        for name in &args.mock_fail {
+            let Some(reader) = READERS.iter().find(|reader| *reader == name) else {
+                eprintln!("unknown mock reader: {}", name);
+                std::process::exit(2);
+            };
+            mock = mock.failing(reader, HwError::missing(format!("mock {}", reader)));
+        }
+        Arc::new(mock)
+    } else {
+        Arc::new(SysfsBackend::new(root.clone(), args.all_fans))
+    };
+
+    if args.mock {
+        logs.info("app", "using mock hardware");
+    } else if !root.is_real() {
+        logs.info("app", format!("using fake root {}", root.path().display()));
+    }
+    let mut app = App::new(backend, logs.clone())?;
+
     let mut terminal = ratatui::init();
-    let app_result = App::default().run(&mut terminal);
+    // clicks on the navbar tabs
+    execute!(io::stdout(), EnableMouseCapture)?;
+    // ratatui's hook (then color-eyre's) only restores what ratatui::init set up
+    let hook = panic::take_hook();
+    panic::set_hook(Box::new(move |info| {
+        let _ = execute!(io::stdout(), DisableMouseCapture);
+        hook(info);
+    }));
+    let app_result = app.run(&mut terminal);
+    execute!(io::stdout(), DisableMouseCapture)?;
     ratatui::restore();
-    app_result
+    // the sinks are written from their own thread
+    logs.flush();
+    Ok(app_result?)
 }
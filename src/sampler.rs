@@ -0,0 +1,486 @@
+use std::{
+    io,
+    sync::{
+        Arc, Mutex,
+        mpsc::{self, Receiver},
+    },
+    thread,
+    time::{Duration, Instant},
+};
+
+use tracing::{debug_span, info, warn};
+
+use crate::{
+    fan_curve::{CurveSource, CurvesHandle},
+    hardware::{
+        Backend, FanInfo, FanMode,
+        cpufreq::{CoreFreq, CpuPolicy},
+        error::{HwError, HwResult},
+        gpu::{GpuState, GpuStatus},
+        hwmon::{self, TempSensor},
+        meminfo::MemInfo,
+        power_supply::PowerSupplies,
+        process::{self, ProcessInfo},
+        rapl::{self, RaplZone, ZonePower},
+    },
+    history::HistoryHandle,
+};
+
+// One reading plus when it was taken.
+#[derive(Clone, Debug)]
+pub struct Sample<T> {
+    pub value: HwResult<T>,
+    pub taken: Instant,
+}
+
+impl<T> Sample<T> {
+    pub fn new(value: HwResult<T>) -> Self {
+        Self {
+            value,
+            taken: Instant::now(),
+        }
+    }
+
+    pub fn error(&self) -> Option<&HwError> {
+        self.value.as_ref().err()
+    }
+}
+
+impl<T: Default> Default for Sample<T> {
+    fn default() -> Self {
+        Self::new(Ok(T::default()))
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct FanReading {
+    pub id: usize,
+    pub label: String,
+    pub speed: HwResult<u16>,
+    pub target: HwResult<u16>,
+    pub max: HwResult<u16>,
+}
+
+// Everything the widgets draw from. The sampler thread builds a new one
+// whenever a sensor is due and hands it over, draws never touch hardware.
+#[derive(Clone, Debug, Default)]
+pub struct Snapshot {
+    // every fan the backend knows about
+    pub fan_list: Sample<Vec<FanInfo>>,
+    pub fans: Vec<FanReading>,
+    pub fan_mode: Sample<FanMode>,
+    pub platform_profile: Sample<String>,
+    pub platform_profile_choices: Sample<Vec<String>>,
+    pub temp_sensors: Sample<Vec<TempSensor>>,
+    // read from `cpu_sensor`, an id out of temp_sensors
+    pub cpu_temp: Sample<u64>,
+    pub cpu_sensor: Option<String>,
+    pub gpus: Sample<Vec<GpuStatus>>,
+    // hottest awake card that reports a temperature, None when all are asleep
+    pub gpu_temp: Sample<Option<u64>>,
+    pub memory: Sample<MemInfo>,
+    pub cpu_freq: Sample<Vec<CoreFreq>>,
+    pub cpu_usage: Sample<Vec<f32>>,
+    pub cpu_policies: Sample<Vec<CpuPolicy>>,
+    pub cpu_boost: Sample<bool>,
+    pub power: Sample<PowerSupplies>,
+    // raw counters, and watts since the previous read of them
+    pub rapl: Sample<Vec<RaplZone>>,
+    pub cpu_power: Vec<ZonePower>,
+    // busiest first
+    pub processes: Sample<Vec<ProcessInfo>>,
+}
+
+impl Snapshot {
+    pub fn fan(&self, fan: usize) -> Option<&FanReading> {
+        self.fans.iter().find(|reading| reading.id == fan)
+    }
+
+    // what a fan curve on `source` sees, None if the sensor failed
+    pub fn curve_temp(&self, source: CurveSource) -> Option<f64> {
+        match source {
+            CurveSource::Cpu => self.cpu_temp.value.as_ref().ok().map(|&t| t as f64),
+            // a sleeping gpu is as cold as it gets as far as curves are concerned
+            CurveSource::Gpu => match self.gpu_temp.value {
+                Ok(Some(temp)) => Some(temp as f64),
+                Ok(None) => Some(0.),
+                Err(_) => None,
+            },
+        }
+    }
+
+    // every failing reader, labelled and with the subsystem it belongs to
+    pub fn errors(&self) -> Vec<(&'static str, String)> {
+        let mut errors = Vec::new();
+
+        for fan in &self.fans {
+            if let Err(err) = &fan.speed {
+                errors.push(("fans", format!("fan {}: {}", fan.id, err)));
+            }
+            if let Err(err) = &fan.target {
+                errors.push(("fans", format!("fan {} target: {}", fan.id, err)));
+            }
+        }
+
+        if let Ok(power) = &self.power.value {
+            for (name, err) in &power.unreadable {
+                errors.push(("power", format!("battery {}: {}", name, err)));
+            }
+        }
+
+        for gpu in self.gpus.value.as_deref().unwrap_or_default() {
+            if let Err(err) = &gpu.state {
+                errors.push((
+                    "gpu",
+                    format!("{} ({}): {}", gpu.card.name, gpu.card.driver, err),
+                ));
+            }
+        }
+
+        let labelled = [
+            ("fans", "fan list", self.fan_list.error()),
+            ("fans", "fan mode", self.fan_mode.error()),
+            ("profile", "platform profile", self.platform_profile.error()),
+            ("sensors", "temperatures", self.temp_sensors.error()),
+            ("sensors", "cpu temp", self.cpu_temp.error()),
+            ("gpu", "gpus", self.gpus.error()),
+            ("memory", "memory", self.memory.error()),
+            ("cpu", "cpu frequency", self.cpu_freq.error()),
+            ("cpu", "cpu usage", self.cpu_usage.error()),
+            ("cpu", "cpufreq policies", self.cpu_policies.error()),
+            ("cpu", "cpu boost", self.cpu_boost.error()),
+            ("power", "power supply", self.power.error()),
+            ("power", "cpu power (RAPL)", self.rapl.error()),
+            ("processes", "processes", self.processes.error()),
+        ];
+
+        errors.extend(labelled.into_iter().filter_map(|(source, label, err)| {
+            err.map(|err| (source, format!("{}: {}", label, err)))
+        }));
+
+        errors
+    }
+}
+
+// How often each sensor gets read.
+#[derive(Clone, Copy, Debug)]
+pub struct Intervals {
+    pub fans: Duration,
+    pub platform_profile: Duration,
+    pub temps: Duration,
+    // nvidia-smi prints once a second on its own, no point in going faster
+    pub gpu: Duration,
+    pub memory: Duration,
+    pub cpu_freq: Duration,
+    // /proc/stat counts 10ms jiffies, so 200ms between reads gives 5% steps
+    pub cpu_usage: Duration,
+    // governor / EPP / boost, only change when someone writes them
+    pub cpu_policy: Duration,
+    pub power: Duration,
+    pub rapl: Duration,
+    pub processes: Duration,
+}
+
+impl Default for Intervals {
+    fn default() -> Self {
+        Self {
+            fans: Duration::from_millis(500),
+            platform_profile: Duration::from_millis(2000),
+            temps: Duration::from_millis(1000),
+            gpu: Duration::from_millis(1000),
+            memory: Duration::from_millis(1000),
+            cpu_freq: Duration::from_millis(200),
+            cpu_usage: Duration::from_millis(200),
+            cpu_policy: Duration::from_millis(2000),
+            power: Duration::from_millis(2000),
+            rapl: Duration::from_millis(1000),
+            processes: Duration::from_millis(2000),
+        }
+    }
+}
+
+// reads are sequential, so one slower than this holds up every other sensor
+const SLOW_READ: Duration = Duration::from_millis(250);
+
+#[derive(Clone, Copy, Debug)]
+enum Sensor {
+    Fans,
+    PlatformProfile,
+    Temps,
+    Gpu,
+    Memory,
+    CpuFreq,
+    CpuUsage,
+    CpuPolicy,
+    Power,
+    Rapl,
+    Processes,
+}
+
+// Knobs the UI can turn while the sampler runs.
+#[derive(Clone, Debug, Default)]
+pub struct Settings {
+    // TempSensor::id to use as "CPU", None picks one automatically
+    pub cpu_sensor: Option<String>,
+}
+
+pub struct Sampler {
+    rx: Receiver<Arc<Snapshot>>,
+    settings: Arc<Mutex<Settings>>,
+}
+
+impl Sampler {
+    // every snapshot is also recorded into `history`, and drives the enabled
+    // fan `curves`
+    pub fn spawn(
+        backend: Backend,
+        intervals: Intervals,
+        history: HistoryHandle,
+        curves: CurvesHandle,
+    ) -> io::Result<Self> {
+        let (tx, rx) = mpsc::channel();
+        let settings = Arc::new(Mutex::new(Settings::default()));
+        let thread_settings = settings.clone();
+
+        thread::Builder::new()
+            .name("sampler".to_string())
+            .spawn(move || {
+                let mut tasks = [
+                    (Sensor::Fans, intervals.fans),
+                    (Sensor::PlatformProfile, intervals.platform_profile),
+                    (Sensor::Temps, intervals.temps),
+                    (Sensor::Gpu, intervals.gpu),
+                    (Sensor::Memory, intervals.memory),
+                    (Sensor::CpuFreq, intervals.cpu_freq),
+                    (Sensor::CpuUsage, intervals.cpu_usage),
+                    (Sensor::CpuPolicy, intervals.cpu_policy),
+                    (Sensor::Power, intervals.power),
+                    (Sensor::Rapl, intervals.rapl),
+                    (Sensor::Processes, intervals.processes),
+                ]
+                .map(|(sensor, interval)| (sensor, interval, Instant::now()));
+                info!("sampler started");
+
+                let mut snapshot = Snapshot::default();
+
+                loop {
+                    let now = Instant::now();
+                    let mut changed = false;
+
+                    for (sensor, interval, due) in &mut tasks {
+                        if *due > now {
+                            continue;
+                        }
+
+                        let settings = thread_settings.lock().unwrap().clone();
+                        let _span = debug_span!("sample", sensor = ?sensor).entered();
+
+                        let started = Instant::now();
+                        sample(&backend, &settings, *sensor, &mut snapshot);
+                        let took = started.elapsed();
+                        if took > SLOW_READ {
+                            warn!(took_ms = took.as_millis() as u64, "slow read");
+                        }
+                        *due = now + *interval;
+                        changed = true;
+                    }
+
+                    if changed {
+                        history.record(&snapshot);
+                        curves.run(&backend, &snapshot, Instant::now());
+                    }
+
+                    // app is gone, so are we
+                    if changed && tx.send(Arc::new(snapshot.clone())).is_err() {
+                        info!("app is gone, sampler stopping");
+                        return;
+                    }
+
+                    let next_due = tasks.iter().map(|(_, _, due)| *due).min().unwrap();
+                    thread::sleep(next_due.saturating_duration_since(Instant::now()));
+                }
+            })?;
+
+        Ok(Self { rx, settings })
+    }
+
+    // takes effect on the next temperature sample
+    pub fn set_cpu_sensor(&self, id: Option<String>) {
+        self.settings.lock().unwrap().cpu_sensor = id;
+    }
+
+    // newest snapshot published since the last call, if any
+    pub fn latest(&self) -> Option<Arc<Snapshot>> {
+        self.rx.try_iter().last()
+    }
+}
+
+fn sample(backend: &Backend, settings: &Settings, sensor: Sensor, snapshot: &mut Snapshot) {
+    match sensor {
+        Sensor::Fans => {
+            snapshot.fan_list = Sample::new(backend.fans());
+
+            let fans = snapshot.fan_list.value.clone().unwrap_or_default();
+            snapshot.fans = fans
+                .into_iter()
+                .map(|FanInfo { id, label, .. }| FanReading {
+                    id,
+                    label,
+                    speed: backend.fan_speed(id),
+                    target: backend.fan_target(id),
+                    max: backend.fan_max(id),
+                })
+                .collect();
+            snapshot.fan_mode = Sample::new(backend.fan_mode());
+        }
+        Sensor::PlatformProfile => {
+            snapshot.platform_profile = Sample::new(backend.platform_profile());
+            snapshot.platform_profile_choices = Sample::new(backend.platform_profile_choices());
+        }
+        Sensor::Temps => {
+            snapshot.temp_sensors = Sample::new(backend.temp_sensors());
+
+            let sensors = snapshot.temp_sensors.value.as_deref().unwrap_or_default();
+            let cpu = match &settings.cpu_sensor {
+                Some(id) => sensors.iter().find(|sensor| sensor.id == *id),
+                None => hwmon::default_cpu_sensor(sensors),
+            };
+
+            snapshot.cpu_sensor = cpu.map(|sensor| sensor.id.clone());
+            snapshot.cpu_temp = Sample::new(match cpu {
+                Some(sensor) => Ok(sensor.input.max(0.) as u64),
+                None => Err(match &settings.cpu_sensor {
+                    Some(id) => HwError::missing(format!("temperature sensor {}", id)),
+                    None => HwError::missing("cpu temperature sensor"),
+                }),
+            });
+        }
+        Sensor::Gpu => {
+            snapshot.gpus = Sample::new(backend.gpus());
+
+            let gpus = snapshot.gpus.value.as_deref().unwrap_or_default();
+            let hottest = gpus
+                .iter()
+                .filter_map(|gpu| match &gpu.state {
+                    Ok(GpuState::Active(reading)) => reading.temp,
+                    _ => None,
+                })
+                .reduce(f64::max);
+            let asleep = gpus.iter().all(|gpu| gpu.state == Ok(GpuState::Suspended));
+
+            snapshot.gpu_temp = Sample::new(match (&snapshot.gpus.value, hottest) {
+                (Err(err), _) => Err(err.clone()),
+                (Ok(_), Some(temp)) => Ok(Some(temp.max(0.) as u64)),
+                (Ok(_), None) if asleep => Ok(None),
+                (Ok(_), None) => Err(HwError::missing("gpu temperature")),
+            });
+        }
+        Sensor::Memory => snapshot.memory = Sample::new(backend.memory()),
+        Sensor::CpuFreq => snapshot.cpu_freq = Sample::new(backend.cpu_freqs()),
+        Sensor::CpuUsage => snapshot.cpu_usage = Sample::new(backend.cpu_usage()),
+        Sensor::CpuPolicy => {
+            snapshot.cpu_policies = Sample::new(backend.cpu_policies());
+            snapshot.cpu_boost = Sample::new(backend.cpu_boost());
+        }
+        Sensor::Power => snapshot.power = Sample::new(backend.power_supplies()),
+        Sensor::Rapl => {
+            let next = Sample::new(backend.rapl_zones());
+
+            snapshot.cpu_power = match (&snapshot.rapl.value, &next.value) {
+                (Ok(prev), Ok(zones)) => {
+                    rapl::power_between(prev, zones, next.taken - snapshot.rapl.taken)
+                }
+                _ => Vec::new(),
+            };
+            snapshot.rapl = next;
+        }
+        Sensor::Processes => {
+            let mut processes = backend.processes();
+            if let Ok(processes) = &mut processes {
+                process::sort_by_usage(processes);
+            }
+            snapshot.processes = Sample::new(processes);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use super::*;
+    use crate::hardware::mock::MockBackend;
+
+    fn read(backend: &Backend, sensor: Sensor, snapshot: &mut Snapshot) {
+        sample(backend, &Settings::default(), sensor, snapshot);
+    }
+
+    #[test]
+    fn fans_are_read_per_script() {
+        let backend: Backend = Arc::new(MockBackend::new().fan(1, 5800, vec![2600, 2700]));
+        let mut snapshot = Snapshot::default();
+
+        read(&backend, Sensor::Fans, &mut snapshot);
+        let fan = snapshot.fan(1).unwrap();
+        assert_eq!(fan.speed, Ok(2600));
+        assert_eq!(fan.max, Ok(5800));
+
+        read(&backend, Sensor::Fans, &mut snapshot);
+        assert_eq!(snapshot.fan(1).unwrap().speed, Ok(2700));
+    }
+
+    #[test]
+    fn cpu_temp_comes_from_the_picked_sensor() {
+        let backend: Backend = Arc::new(MockBackend::new().cpu_temps(vec![60]));
+        let mut snapshot = Snapshot::default();
+
+        read(&backend, Sensor::Temps, &mut snapshot);
+        assert_eq!(snapshot.cpu_sensor.as_deref(), Some("hwmon5/temp1"));
+        assert_eq!(snapshot.cpu_temp.value, Ok(60));
+
+        let settings = Settings {
+            cpu_sensor: Some("hwmon0/temp1".to_string()),
+        };
+        sample(&backend, &settings, Sensor::Temps, &mut snapshot);
+        assert_eq!(snapshot.cpu_temp.value, Ok(52));
+    }
+
+    #[test]
+    fn sleeping_gpus_have_no_temperature() {
+        let backend: Backend = Arc::new(MockBackend::new().gpu(
+            "card0",
+            "0000:01:00.0",
+            "nvidia",
+            vec![(0, 0, 0), (40, 55, 1500)],
+        ));
+        let mut snapshot = Snapshot::default();
+
+        read(&backend, Sensor::Gpu, &mut snapshot);
+        assert_eq!(snapshot.gpu_temp.value, Ok(None));
+
+        read(&backend, Sensor::Gpu, &mut snapshot);
+        assert_eq!(snapshot.gpu_temp.value, Ok(Some(55)));
+    }
+
+    #[test]
+    fn failures_are_reported_with_their_source() {
+        let backend: Backend = Arc::new(
+            MockBackend::new()
+                .fan(1, 5800, vec![2600])
+                .failing("fan_speed", HwError::missing("mock fan_speed"))
+                .failing("memory", HwError::missing("mock memory")),
+        );
+        let mut snapshot = Snapshot::default();
+
+        read(&backend, Sensor::Fans, &mut snapshot);
+        read(&backend, Sensor::Memory, &mut snapshot);
+
+        assert_eq!(
+            snapshot.errors(),
+            [
+                ("fans", "fan 1: missing device: mock fan_speed".to_string()),
+                ("memory", "memory: missing device: mock memory".to_string()),
+            ]
+        );
+    }
+}
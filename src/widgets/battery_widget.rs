@@ -0,0 +1,404 @@
+use std::time::{Duration, Instant};
+
+use crossterm::event::{KeyCode, KeyEvent};
+use ratatui::{
+    buffer::Buffer,
+    layout::{Constraint, Layout, Rect},
+    style::{Color, Style, Stylize},
+    text::{Line, Span},
+    widgets::{Block, BorderType, Borders, Gauge, Paragraph, Widget},
+};
+
+use crate::{
+    hardware::{
+        Backend,
+        error::HwResult,
+        power_supply::{Battery, ChargeThreshold, PowerSupplies},
+    },
+    logs::LogHandle,
+    margin,
+    sampler::Snapshot,
+    widgets::panel::{Action, Panel},
+};
+
+const THRESHOLD_STEP: u8 = 5;
+// lowest end threshold we'll write, below it the battery is barely usable
+const MIN_END: u8 = 50;
+
+fn hours(duration: Duration) -> String {
+    let minutes = duration.as_secs() / 60;
+    format!("{}h{:02}m", minutes / 60, minutes % 60)
+}
+
+fn capacity_color(capacity: u8) -> Color {
+    match capacity {
+        0..=15 => Color::Red,
+        16..=35 => Color::Yellow,
+        _ => Color::Green,
+    }
+}
+
+// what `which` may be set to: start stays below end, end above start and
+// MIN_END
+fn threshold_range(battery: &Battery, which: ChargeThreshold) -> (u8, u8) {
+    match which {
+        ChargeThreshold::Start => (0, battery.charge_end.unwrap_or(100).saturating_sub(1)),
+        ChargeThreshold::End => {
+            let above_start = battery
+                .charge_start
+                .map_or(0, |start| start.saturating_add(1));
+            (MIN_END.max(above_start), 100)
+        }
+    }
+}
+
+// Charge, health and draw of every battery, plus an editor for the charge
+// thresholds where the firmware has them.
+pub struct BatteryWidget {
+    pub title: &'static str,
+    pub focused: bool,
+    backend: Backend,
+    logs: LogHandle,
+
+    supplies: HwResult<PowerSupplies>,
+    // when `supplies` was sampled, so writes aren't undone by an older sample
+    taken: Option<Instant>,
+    // battery and threshold being edited, and the not yet written value
+    battery: usize,
+    threshold: ChargeThreshold,
+    pending: Option<u8>,
+}
+
+impl BatteryWidget {
+    pub fn new(title: &'static str, backend: Backend, logs: LogHandle) -> Self {
+        Self {
+            title,
+            focused: false,
+            backend,
+            logs,
+            supplies: Ok(PowerSupplies::default()),
+            taken: None,
+            battery: 0,
+            threshold: ChargeThreshold::End,
+            pending: None,
+        }
+    }
+
+    pub fn update(&mut self, snapshot: &Snapshot) {
+        if self.taken == Some(snapshot.power.taken) {
+            return;
+        }
+        self.taken = Some(snapshot.power.taken);
+        self.supplies = snapshot.power.value.clone();
+    }
+
+    fn batteries(&self) -> &[Battery] {
+        self.supplies
+            .as_ref()
+            .map(|supplies| supplies.batteries.as_slice())
+            .unwrap_or_default()
+    }
+
+    fn selected(&self) -> Option<&Battery> {
+        self.batteries().get(self.battery)
+    }
+
+    // thresholds the selected battery actually has
+    fn thresholds(&self) -> Vec<ChargeThreshold> {
+        let Some(battery) = self.selected() else {
+            return Vec::new();
+        };
+
+        [ChargeThreshold::Start, ChargeThreshold::End]
+            .into_iter()
+            .filter(|which| battery.threshold(*which).is_some())
+            .collect()
+    }
+
+    fn step(&mut self, up: bool) {
+        let Some(battery) = self.selected() else {
+            return;
+        };
+        let Some(current) = battery.threshold(self.threshold) else {
+            return;
+        };
+        let (min, max) = threshold_range(battery, self.threshold);
+
+        let value = self.pending.unwrap_or(current);
+        let value = match up {
+            true => value.saturating_add(THRESHOLD_STEP),
+            false => value.saturating_sub(THRESHOLD_STEP),
+        };
+        self.pending = Some(value.max(min).min(max));
+    }
+
+    fn write_pending(&mut self) {
+        let Some(value) = self.pending.take() else {
+            return;
+        };
+        let Some(battery) = self.selected() else {
+            return;
+        };
+        let name = battery.name.clone();
+        let Some(old) = battery.threshold(self.threshold) else {
+            return;
+        };
+
+        let which = self.threshold;
+
+        // the other threshold may have changed since the step
+        let (min, max) = threshold_range(battery, which);
+        if value < min || value > max {
+            self.logs.warn(
+                "power",
+                format!(
+                    "{} charge {} threshold: not setting {}%, it has to be {}-{}%",
+                    name, which, value, min, max
+                ),
+            );
+            return;
+        }
+
+        if let Err(err) = self.backend.set_charge_threshold(&name, which, value) {
+            self.logs.error(
+                "power",
+                format!(
+                    "{} charge {} threshold: setting {}% failed: {}",
+                    name, which, value, err
+                ),
+            );
+            return;
+        }
+
+        // show it right away instead of after the next sample
+        if let Ok(supplies) = &mut self.supplies
+            && let Some(battery) = supplies.batteries.get_mut(self.battery)
+        {
+            match which {
+                ChargeThreshold::Start => battery.charge_start = Some(value),
+                ChargeThreshold::End => battery.charge_end = Some(value),
+            }
+        }
+
+        self.logs.info(
+            "power",
+            format!(
+                "{} charge {} threshold: {}% -> {}%",
+                name, which, old, value
+            ),
+        );
+    }
+
+    fn handle_key(&mut self, key_event: KeyEvent) {
+        match key_event.code {
+            KeyCode::Up | KeyCode::Down => {
+                let thresholds = self.thresholds();
+                if let Some(pos) = thresholds.iter().position(|t| *t == self.threshold) {
+                    let next = match key_event.code {
+                        KeyCode::Up => pos.saturating_sub(1),
+                        _ => (pos + 1).min(thresholds.len() - 1),
+                    };
+                    self.threshold = thresholds[next];
+                } else if let Some(first) = thresholds.first() {
+                    self.threshold = *first;
+                }
+                self.pending = None;
+            }
+            KeyCode::Left => self.step(false),
+            KeyCode::Right => self.step(true),
+            KeyCode::Enter => self.write_pending(),
+            KeyCode::Char('n') => {
+                self.battery = (self.battery + 1) % self.batteries().len().max(1);
+                self.pending = None;
+            }
+            KeyCode::Esc => self.pending = None,
+            _ => {}
+        }
+    }
+
+    fn render_battery(&self, battery: &Battery, selected: bool, area: Rect, buf: &mut Buffer) {
+        let rows: [Rect; 5] = Layout::vertical([Constraint::Length(1); 5]).areas(area);
+
+        let mut title = vec![battery.name.clone().bold(), " ".into()];
+        if !battery.model.is_empty() {
+            title.push(format!("{} ", battery.model).dark_gray());
+        }
+        title.push(battery.status.clone().into());
+        Line::from(title).render(rows[0], buf);
+
+        let color = capacity_color(battery.capacity);
+        Gauge::default()
+            .percent(u16::from(battery.capacity.min(100)))
+            .label(format!("{}%", battery.capacity))
+            .gauge_style(Style::new().fg(color).bg(Color::DarkGray))
+            .render(rows[1], buf);
+
+        let mut health = format!(
+            "{:.1} / {:.1} Wh (design {:.1} Wh)",
+            battery.energy_now, battery.energy_full, battery.energy_full_design
+        );
+        if let Some(wear) = battery.wear() {
+            health.push_str(&format!("  wear {:.1}%", wear));
+        }
+        if let Some(cycles) = battery.cycle_count {
+            health.push_str(&format!("  {} cycles", cycles));
+        }
+        Line::from(health).render(rows[2], buf);
+
+        let mut draw = vec![format!("{:.1} W", battery.power_now).bold()];
+        if let Some(left) = battery.time_to_empty() {
+            draw.push(format!("  empty in {}", hours(left)).into());
+        }
+        if let Some(left) = battery.time_to_full() {
+            draw.push(format!("  full in {}", hours(left)).into());
+        }
+        Line::from(draw).render(rows[3], buf);
+
+        let mut spans: Vec<Span> = vec!["charge thresholds: ".dark_gray()];
+        let mut any = false;
+        for which in [ChargeThreshold::Start, ChargeThreshold::End] {
+            let Some(value) = battery.threshold(which) else {
+                continue;
+            };
+            any = true;
+
+            let editing = self.focused && selected && which == self.threshold;
+            let shown = match (editing, self.pending) {
+                (true, Some(pending)) => format!("{} {}% → {}%", which, value, pending),
+                _ => format!("{} {}%", which, value),
+            };
+            spans.push(match editing {
+                true => shown.reversed(),
+                false => shown.into(),
+            });
+            spans.push("  ".into());
+        }
+        if !any {
+            spans.push("not supported by the firmware".dark_gray());
+        }
+        Line::from(spans).render(rows[4], buf);
+    }
+}
+
+impl Panel for BatteryWidget {
+    fn set_focused(&mut self, focused: bool) {
+        self.focused = focused;
+        self.pending = None;
+    }
+
+    fn handle_key_event(&mut self, key_event: KeyEvent) -> Vec<Action> {
+        self.handle_key(key_event);
+        Vec::new()
+    }
+}
+
+impl Widget for &BatteryWidget {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        let mut block = Block::default()
+            .borders(Borders::ALL)
+            .border_type(BorderType::Rounded)
+            .title(self.title);
+        if self.focused {
+            block = block.border_style(Style::new().yellow()).title_bottom(
+                Line::from(" [↑/↓] threshold  [←/→] ±5%  [Enter] write  [n] next battery ")
+                    .centered(),
+            );
+        }
+        let inner = block.inner(area).inner(margin!(1, 0));
+        block.render(area, buf);
+
+        let supplies = match &self.supplies {
+            Ok(supplies) => supplies,
+            Err(err) => {
+                Paragraph::new(format!("power supply unavailable: {}", err).dark_gray())
+                    .centered()
+                    .render(inner, buf);
+                return;
+            }
+        };
+
+        let ac = match supplies.ac_online {
+            Some(true) => "AC: plugged in".green(),
+            Some(false) => "AC: on battery".yellow(),
+            None => "AC: unknown".dark_gray(),
+        };
+
+        let [ac_area, batteries_area] =
+            Layout::vertical([Constraint::Length(2), Constraint::Fill(1)]).areas(inner);
+        Line::from(ac).render(ac_area, buf);
+
+        if supplies.batteries.is_empty() && supplies.unreadable.is_empty() {
+            Line::from("no battery".dark_gray()).render(batteries_area, buf);
+            return;
+        }
+
+        // the unreadable ones get a line each, after the rest
+        let heights = supplies
+            .batteries
+            .iter()
+            .map(|_| Constraint::Length(6))
+            .chain(supplies.unreadable.iter().map(|_| Constraint::Length(1)));
+        let areas = Layout::vertical(heights).split(batteries_area);
+
+        for (idx, (battery, area)) in supplies.batteries.iter().zip(areas.iter()).enumerate() {
+            self.render_battery(battery, idx == self.battery, *area, buf);
+        }
+        let rest = areas.iter().skip(supplies.batteries.len());
+        for ((name, err), area) in supplies.unreadable.iter().zip(rest) {
+            Line::from(vec![
+                name.clone().bold(),
+                format!(" unreadable: {}", err).dark_gray(),
+            ])
+            .render(*area, buf);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use super::*;
+    use crate::{
+        hardware::{HardwareBackend, mock::MockBackend},
+        sampler::Sample,
+    };
+
+    #[test]
+    fn start_stays_below_end() {
+        let battery = Battery {
+            charge_start: Some(40),
+            charge_end: Some(60),
+            ..Battery::default()
+        };
+
+        assert_eq!(threshold_range(&battery, ChargeThreshold::Start), (0, 59));
+        assert_eq!(threshold_range(&battery, ChargeThreshold::End), (50, 100));
+
+        let battery = Battery {
+            charge_start: Some(75),
+            ..battery
+        };
+        assert_eq!(threshold_range(&battery, ChargeThreshold::End), (76, 100));
+    }
+
+    #[test]
+    fn end_is_not_lowered_past_the_floor() {
+        // end threshold at 80%
+        let mock = Arc::new(MockBackend::new().battery(vec![70]));
+        let mut widget = BatteryWidget::new("Battery", mock.clone(), LogHandle::new());
+        widget.update(&Snapshot {
+            power: Sample::new(mock.power_supplies()),
+            ..Snapshot::default()
+        });
+
+        for _ in 0..10 {
+            widget.handle_key_event(KeyEvent::from(KeyCode::Left));
+        }
+        assert_eq!(widget.pending, Some(MIN_END));
+
+        widget.handle_key_event(KeyEvent::from(KeyCode::Enter));
+        let supplies = mock.power_supplies().unwrap();
+        assert_eq!(supplies.batteries[0].charge_end, Some(MIN_END));
+    }
+}
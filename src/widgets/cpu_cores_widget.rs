@@ -1,47 +1,57 @@
 use ratatui::{
     buffer::Buffer,
+    style::Stylize,
     text::Line,
-    widgets::{Bar, BarChart, BarGroup, Block, BorderType, Borders, Widget},
+    widgets::{Bar, BarChart, BarGroup, Block, BorderType, Borders, Paragraph, Widget},
 };
-use sysinfo::{Cpu, System};
 
-use crate::margin;
+use std::sync::Arc;
+
+use crate::sampler::Snapshot;
 
 pub struct CoresWidget {
     pub title: &'static str,
-    sys: System,
+    snapshot: Arc<Snapshot>,
 }
 
 impl CoresWidget {
     pub fn new(title: &'static str) -> Self {
-        let mut sys = System::new_all();
-        sys.refresh_cpu();
-
-        Self { title, sys }
+        Self {
+            title,
+            snapshot: Arc::default(),
+        }
     }
 
-    // ---------------------------------------------------------
-    // IMPORTANT: Call this function in your main app loop!
-    // e.g. every tick or every 500ms.
-    // ---------------------------------------------------------
-    pub fn update(&mut self) {
-        self.sys.refresh_cpu();
+    pub fn update(&mut self, snapshot: &Arc<Snapshot>) {
+        self.snapshot = snapshot.clone();
     }
 
     pub fn mk_bars(&self) -> Vec<Bar<'_>> {
-        self.sys
-            .cpus()
+        let Ok(usage) = &self.snapshot.cpu_usage.value else {
+            return Vec::new();
+        };
+
+        let freqs = self.snapshot.cpu_freq.value.as_deref().unwrap_or_default();
+
+        usage
             .iter()
             .enumerate()
-            .map(|(i, cpu)| {
-                let usage_percent = cpu.cpu_usage(); // Returns f32 (0.0 to 100.0)
+            .map(|(i, &usage_percent)| {
+                // 0.0 to 100.0
 
                 let bar_value = ((usage_percent / 10.0) as u64).max(1);
 
+                // shown inside the bar, cores without cpufreq just get nothing
+                let freq = freqs
+                    .iter()
+                    .find(|freq| freq.cpu == i)
+                    .map(|freq| format!("{:.1}G", freq.cur))
+                    .unwrap_or_default();
+
                 Bar::default()
                     .value(bar_value)
                     .label(Line::from(format!("cpu{}", i)))
-                    .text_value("".to_string())
+                    .text_value(freq)
             })
             .collect()
     }
@@ -54,6 +64,14 @@ impl Widget for &CoresWidget {
             .border_type(BorderType::Rounded)
             .title(self.title);
 
+        if self.snapshot.cpu_usage.value.is_err() {
+            Paragraph::new("sensor unavailable".dark_gray())
+                .centered()
+                .block(block)
+                .render(area, buf);
+            return;
+        }
+
         let bars = self.mk_bars();
 
         BarChart::default()
@@ -0,0 +1,342 @@
+use std::time::Instant;
+
+use crossterm::event::{KeyCode, KeyEvent};
+use ratatui::{
+    buffer::Buffer,
+    layout::{Constraint, Layout},
+    style::{Style, Stylize},
+    text::{Line, Span},
+    widgets::{Block, BorderType, Borders, Paragraph, Row, Table, Widget},
+};
+
+use crate::{
+    hardware::{Backend, cpufreq::CpuPolicy, error::HwResult},
+    logs::LogHandle,
+    margin,
+    sampler::Snapshot,
+    widgets::panel::{Action, Panel},
+};
+
+#[derive(Clone, Copy, PartialEq)]
+enum Setting {
+    Governor,
+    Epp,
+    Boost,
+}
+
+const SETTINGS: [Setting; 3] = [Setting::Governor, Setting::Epp, Setting::Boost];
+
+impl Setting {
+    fn label(self) -> &'static str {
+        match self {
+            Setting::Governor => "governor",
+            Setting::Epp => "EPP",
+            Setting::Boost => "boost",
+        }
+    }
+}
+
+// the settings written to each cpufreq policy
+#[derive(Clone, Copy)]
+enum PolicyAttr {
+    Governor,
+    Epp,
+}
+
+impl PolicyAttr {
+    // None when the policy doesn't have it
+    fn value(self, policy: &mut CpuPolicy) -> Option<&mut String> {
+        match self {
+            PolicyAttr::Governor => Some(&mut policy.governor),
+            PolicyAttr::Epp => policy.epp.as_mut(),
+        }
+    }
+
+    fn write(self, backend: &Backend, policy: &str, value: &str) -> HwResult<()> {
+        match self {
+            PolicyAttr::Governor => backend.set_governor(policy, value),
+            PolicyAttr::Epp => backend.set_epp(policy, value),
+        }
+    }
+}
+
+// values every policy accepts, in the order the first one lists them
+fn common(lists: impl Iterator<Item = Vec<String>>) -> Vec<String> {
+    let mut lists = lists;
+    let Some(first) = lists.next() else {
+        return Vec::new();
+    };
+
+    let rest: Vec<Vec<String>> = lists.collect();
+    first
+        .into_iter()
+        .filter(|value| rest.iter().all(|list| list.contains(value)))
+        .collect()
+}
+
+// Governor, energy performance preference and boost. Choices apply to every
+// cpufreq policy at once, each write gets its own log line.
+pub struct CpuPowerWidget {
+    pub title: &'static str,
+    pub focused: bool,
+    backend: Backend,
+    logs: LogHandle,
+
+    policies: HwResult<Vec<CpuPolicy>>,
+    boost: HwResult<bool>,
+    // when `policies` was sampled, so writes aren't undone by an older sample
+    taken: Option<Instant>,
+
+    selected: usize,
+    // highlighted choice of each setting
+    cursor: [usize; 3],
+}
+
+impl CpuPowerWidget {
+    pub fn new(title: &'static str, backend: Backend, logs: LogHandle) -> Self {
+        Self {
+            title,
+            focused: false,
+            backend,
+            logs,
+            policies: Ok(Vec::new()),
+            boost: Ok(false),
+            taken: None,
+            selected: 0,
+            cursor: [0; 3],
+        }
+    }
+
+    pub fn update(&mut self, snapshot: &Snapshot) {
+        if self.taken == Some(snapshot.cpu_policies.taken) {
+            return;
+        }
+        self.taken = Some(snapshot.cpu_policies.taken);
+        self.policies = snapshot.cpu_policies.value.clone();
+        self.boost = snapshot.cpu_boost.value.clone();
+    }
+
+    fn policies(&self) -> &[CpuPolicy] {
+        self.policies.as_deref().unwrap_or_default()
+    }
+
+    fn choices(&self, setting: Setting) -> Vec<String> {
+        match setting {
+            Setting::Governor => common(self.policies().iter().map(|p| p.governors.clone())),
+            Setting::Epp => common(
+                self.policies()
+                    .iter()
+                    .filter(|p| p.epp.is_some())
+                    .map(|p| p.epp_choices.clone()),
+            ),
+            Setting::Boost => match self.boost {
+                Ok(_) => vec!["on".to_string(), "off".to_string()],
+                Err(_) => Vec::new(),
+            },
+        }
+    }
+
+    // the value every policy agrees on, None when they differ
+    fn current(&self, setting: Setting) -> Option<String> {
+        let values: Vec<String> = match setting {
+            Setting::Governor => self.policies().iter().map(|p| p.governor.clone()).collect(),
+            Setting::Epp => self
+                .policies()
+                .iter()
+                .filter_map(|p| p.epp.clone())
+                .collect(),
+            Setting::Boost => {
+                let boost = self.boost.clone().ok()?;
+                vec![if boost { "on" } else { "off" }.to_string()]
+            }
+        };
+
+        let first = values.first()?;
+        values.iter().all(|v| v == first).then(|| first.clone())
+    }
+
+    // puts each cursor on the current value
+    fn reset_cursors(&mut self) {
+        for (idx, setting) in SETTINGS.into_iter().enumerate() {
+            let current = self.current(setting);
+            self.cursor[idx] = self
+                .choices(setting)
+                .iter()
+                .position(|choice| Some(choice) == current.as_ref())
+                .unwrap_or(0);
+        }
+    }
+
+    fn apply(&mut self, setting: Setting, value: &str) {
+        match setting {
+            Setting::Governor => self.apply_policies(setting, PolicyAttr::Governor, value),
+            Setting::Epp => self.apply_policies(setting, PolicyAttr::Epp, value),
+            Setting::Boost => self.apply_boost(value),
+        }
+    }
+
+    fn apply_boost(&mut self, value: &str) {
+        let enabled = value == "on";
+        match self.backend.set_cpu_boost(enabled) {
+            Ok(()) => {
+                self.boost = Ok(enabled);
+                self.logs.info("cpu", format!("boost: {}", value));
+            }
+            Err(err) => self
+                .logs
+                .error("cpu", format!("boost: turning {} failed: {}", value, err)),
+        }
+    }
+
+    fn apply_policies(&mut self, setting: Setting, attr: PolicyAttr, value: &str) {
+        let logs = &self.logs;
+        let label = setting.label();
+
+        let Ok(policies) = &mut self.policies else {
+            logs.warn("cpu", "cpufreq policies: unavailable");
+            return;
+        };
+
+        for policy in policies.iter_mut() {
+            let name = policy.name.clone();
+            let Some(old) = attr.value(policy) else {
+                continue;
+            };
+
+            match attr.write(&self.backend, &name, value) {
+                Ok(()) if old == value => {
+                    logs.info("cpu", format!("{} {}: already {}", name, label, value))
+                }
+                Ok(()) => {
+                    logs.info("cpu", format!("{} {}: {} -> {}", name, label, old, value));
+                    *old = value.to_string();
+                }
+                Err(err) => logs.error(
+                    "cpu",
+                    format!("{} {}: setting {} failed: {}", name, label, value, err),
+                ),
+            }
+        }
+    }
+}
+
+impl Panel for CpuPowerWidget {
+    fn set_focused(&mut self, focused: bool) {
+        self.focused = focused;
+        if focused {
+            self.reset_cursors();
+        }
+    }
+
+    fn handle_key_event(&mut self, key_event: KeyEvent) -> Vec<Action> {
+        let setting = SETTINGS[self.selected];
+        let count = self.choices(setting).len();
+        let cursor = &mut self.cursor[self.selected];
+
+        match key_event.code {
+            KeyCode::Up => self.selected = self.selected.saturating_sub(1),
+            KeyCode::Down => self.selected = (self.selected + 1).min(SETTINGS.len() - 1),
+            KeyCode::Left => *cursor = cursor.saturating_sub(1),
+            KeyCode::Right => *cursor = (*cursor + 1).min(count.saturating_sub(1)),
+            KeyCode::Enter => {
+                let cursor = *cursor;
+                if let Some(value) = self.choices(setting).get(cursor).cloned() {
+                    self.apply(setting, &value);
+                }
+            }
+            _ => {}
+        }
+
+        Vec::new()
+    }
+}
+
+impl Widget for &CpuPowerWidget {
+    fn render(self, area: ratatui::prelude::Rect, buf: &mut Buffer) {
+        let mut block = Block::default()
+            .borders(Borders::ALL)
+            .border_type(BorderType::Rounded)
+            .title(self.title);
+        if self.focused {
+            block = block.border_style(Style::new().yellow()).title_bottom(
+                Line::from(" [↑/↓] setting  [←/→] value  [Enter] apply to all cores ").centered(),
+            );
+        }
+        let inner = block.inner(area);
+        block.render(area, buf);
+
+        if let Err(err) = &self.policies {
+            Paragraph::new(format!("cpufreq unavailable: {}", err).dark_gray())
+                .centered()
+                .render(inner.inner(margin!(1, 1)), buf);
+            return;
+        }
+
+        let [settings_area, table_area] =
+            Layout::vertical([Constraint::Length(4), Constraint::Fill(1)])
+                .areas(inner.inner(margin!(1, 0)));
+
+        let lines: Vec<Line> = SETTINGS
+            .into_iter()
+            .enumerate()
+            .map(|(idx, setting)| {
+                let selected = self.focused && idx == self.selected;
+                let current = self.current(setting);
+                let choices = self.choices(setting);
+
+                let marker = if selected { "▶ " } else { "  " };
+                let mut spans: Vec<Span> =
+                    vec![marker.cyan(), format!("{:<10}", setting.label()).bold()];
+
+                if choices.is_empty() {
+                    spans.push("not supported".dark_gray());
+                }
+                for (pos, choice) in choices.iter().enumerate() {
+                    let mut span = match Some(choice) == current.as_ref() {
+                        true => Span::from(format!("[{}]", choice)).yellow().bold(),
+                        false => Span::from(choice.clone()),
+                    };
+                    if selected && pos == self.cursor[idx] {
+                        span = span.reversed();
+                    }
+                    spans.push(span);
+                    spans.push(" ".into());
+                }
+                if current.is_none() && !choices.is_empty() {
+                    spans.push("(mixed)".dark_gray());
+                }
+
+                Line::from(spans)
+            })
+            .collect();
+
+        Paragraph::new(lines).render(settings_area, buf);
+
+        let rows: Vec<Row> = self
+            .policies()
+            .iter()
+            .map(|policy| {
+                Row::new(vec![
+                    policy.name.clone(),
+                    policy.cpus.clone(),
+                    policy.driver.clone(),
+                    policy.governor.clone(),
+                    policy.epp.clone().unwrap_or_else(|| "-".to_string()),
+                ])
+            })
+            .collect();
+
+        Table::new(
+            rows,
+            [
+                Constraint::Length(10),
+                Constraint::Length(8),
+                Constraint::Length(16),
+                Constraint::Length(14),
+                Constraint::Fill(1),
+            ],
+        )
+        .header(Row::new(vec!["policy", "cpus", "driver", "governor", "EPP"]).bold())
+        .render(table_area, buf);
+    }
+}
@@ -0,0 +1,147 @@
+use ratatui::{
+    buffer::Buffer,
+    layout::{Constraint, Layout},
+    style::{Style, Stylize},
+    symbols::Marker,
+    text::Line,
+    widgets::{Axis, Block, BorderType, Borders, Chart, Dataset, GraphType, Paragraph, Widget},
+};
+
+use crate::fan_curve::{CurveController, CurveUnit, TEMP_MAX, TEMP_MIN};
+
+// Borrowed view of one fan's curve, drawn over the fans panel while editing.
+pub struct CurveEditor<'a> {
+    pub fan_id: usize,
+    pub controller: &'a CurveController,
+    pub max: u16,
+    pub selected: usize,
+    // current temperature of the curve's source, if known
+    pub live: Option<f64>,
+}
+
+impl Widget for CurveEditor<'_> {
+    fn render(self, area: ratatui::prelude::Rect, buf: &mut Buffer) {
+        let curve = &self.controller.curve;
+
+        let state = match self.controller.enabled {
+            true => "enabled".green().bold(),
+            false => "disabled".dark_gray(),
+        };
+
+        let block = Block::default()
+            .borders(Borders::ALL)
+            .border_type(BorderType::Rounded)
+            .border_style(Style::new().cyan())
+            .title(Line::from(vec![
+                format!(" fan {} curve ({}) ", self.fan_id, curve.source).bold(),
+                state,
+                " ".into(),
+            ]))
+            .title_bottom(
+                Line::from(
+                    " ←/→ point  ⇧←/→ ºC  ↑/↓ speed  s source  u unit  \
+                     h/H hysteresis  d/D delay  e on/off  Esc close ",
+                )
+                .centered(),
+            );
+
+        let inner = block.inner(area);
+        block.render(area, buf);
+
+        let layout =
+            Layout::vertical(vec![Constraint::Fill(1), Constraint::Length(1)]).split(inner);
+
+        let line: Vec<(f64, f64)> = curve.points.clone();
+        let selected: Vec<(f64, f64)> = curve
+            .points
+            .get(self.selected)
+            .into_iter()
+            .copied()
+            .collect();
+        let live: Vec<(f64, f64)> = self
+            .live
+            .map(|temp| (temp, curve.eval(temp)))
+            .into_iter()
+            .collect();
+
+        let datasets = vec![
+            Dataset::default()
+                .name("curve")
+                .marker(Marker::Braille)
+                .graph_type(GraphType::Line)
+                .style(Style::new().cyan())
+                .data(&line),
+            Dataset::default()
+                .marker(Marker::Dot)
+                .graph_type(GraphType::Scatter)
+                .style(Style::new().cyan())
+                .data(&line),
+            Dataset::default()
+                .name("selected")
+                .marker(Marker::Block)
+                .graph_type(GraphType::Scatter)
+                .style(Style::new().yellow())
+                .data(&selected),
+            Dataset::default()
+                .name("now")
+                .marker(Marker::Block)
+                .graph_type(GraphType::Scatter)
+                .style(Style::new().red())
+                .data(&live),
+        ];
+
+        let y_max = curve.value_max(self.max);
+        let y_label = match curve.unit {
+            CurveUnit::Rpm => "rpm",
+            CurveUnit::Percent => "% of max",
+        };
+
+        Chart::new(datasets)
+            .x_axis(
+                Axis::default()
+                    .title("ºC")
+                    .style(Style::default().gray())
+                    .bounds([TEMP_MIN, TEMP_MAX])
+                    .labels(
+                        [TEMP_MIN, (TEMP_MIN + TEMP_MAX) / 2., TEMP_MAX]
+                            .map(|temp| format!("{}", temp).bold()),
+                    ),
+            )
+            .y_axis(
+                Axis::default()
+                    .title(y_label)
+                    .style(Style::default().gray())
+                    .bounds([0., y_max])
+                    .labels(["0".bold(), format!("{}", y_max).bold()]),
+            )
+            .render(layout[0], buf);
+
+        let point = curve
+            .points
+            .get(self.selected)
+            .map(|(temp, value)| {
+                format!(
+                    "point {}: {:.0}ºC → {:.0}{}",
+                    self.selected + 1,
+                    temp,
+                    value,
+                    curve.unit
+                )
+            })
+            .unwrap_or_default();
+
+        let now = match self.live {
+            Some(temp) => format!("now {:.0}ºC → {} rpm", temp, curve.rpm(temp, self.max)),
+            None => "now: no temperature".to_string(),
+        };
+
+        Paragraph::new(format!(
+            "{}   hysteresis {:.0}ºC   ramp-down delay {}s   {}",
+            point,
+            curve.hysteresis,
+            curve.ramp_down_delay.as_secs(),
+            now
+        ))
+        .render(layout[1], buf);
+    }
+}
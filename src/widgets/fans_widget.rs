@@ -1,107 +1,150 @@
-use std::{
-    fs::{self, OpenOptions},
-    io::{self, Read, Write},
-    process::Command,
-};
+use std::time::Duration;
 
-use glob::glob;
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
 use ratatui::{
     buffer::Buffer,
     layout::{Constraint, Direction, Layout},
-    style::{Color, Stylize},
-    symbols::Marker,
+    style::{Color, Style, Stylize},
+    text::Line,
     widgets::{
         Block, BorderType, Borders, Paragraph, Widget,
-        canvas::{Canvas, Circle, Line, Map, MapResolution, Rectangle},
+        canvas::{Canvas, Circle},
+    },
+};
+
+use crate::{
+    fan_curve::{CurveController, CurveSource, CurveUnit, CurvesHandle},
+    hardware::{Backend, FanMode, error::HwResult},
+    logs::LogHandle,
+    margin,
+    sampler::Snapshot,
+    widgets::{
+        fan_curve_widget::CurveEditor,
+        panel::{Action, Panel},
     },
 };
 
-use crate::margin;
+// rpm per Up/Down and per PageUp/PageDown
+const FINE_STEP: u16 = 100;
+const COARSE_STEP: u16 = 500;
+
+const FANS_PER_ROW: usize = 3;
 
 pub struct Fan {
     pub id: usize,
+    pub label: String,
+    backend: Backend,
+
+    pub cur_speed: HwResult<u16>,
+    pub target: HwResult<u16>,
+    pub max: HwResult<u16>,
+    pub selected: bool,
+    // pinned at full speed, the target does nothing
+    pub pinned: bool,
 }
 
-// id gonna be hardcoded for now
 impl Fan {
-    pub fn new(id: usize) -> Self {
-        Self { id: id }
+    pub fn new(id: usize, label: String, backend: Backend) -> Self {
+        Self {
+            id,
+            label,
+            backend,
+            cur_speed: Ok(0),
+            target: Ok(0),
+            max: Ok(0),
+            selected: false,
+            pinned: false,
+        }
     }
 
-    pub fn get_max_fan_speed(&self) -> io::Result<u16> {
-        let pattern = format!(
-            "/sys/devices/platform/hp-wmi/hwmon/hwmon*/fan{}_max",
-            self.id
-        );
+    pub fn update(&mut self, snapshot: &Snapshot) {
+        if let Some(reading) = snapshot.fan(self.id) {
+            self.cur_speed = reading.speed.clone();
+            self.target = reading.target.clone();
+            self.max = reading.max.clone();
+        }
+    }
 
-        let path = glob(&pattern)
-            .expect("failed to read fan_max")
-            .filter_map(Result::ok)
-            .next()
-            .expect("no fan max file found");
+    pub fn get_max_fan_speed(&self) -> HwResult<u16> {
+        self.backend.fan_max(self.id)
+    }
 
-        let mut file = OpenOptions::new().read(true).open(path)?;
-        let mut contents = String::new();
-        file.read_to_string(&mut contents)?;
+    // clamps to fan{N}_max, returns what was actually written
+    pub fn set_target_speed(&mut self, speed: u16) -> HwResult<u16> {
+        let speed = speed.min(self.get_max_fan_speed()?);
 
-        let number: u16 = contents
-            .trim()
-            .parse()
-            .expect("failed to parse the number from fan_max");
+        self.backend.set_fan_target(self.id, speed)?;
+        // don't wait for the sampler, the next step builds on this
+        self.target = Ok(speed);
 
-        Ok(number)
+        Ok(speed)
     }
 
-    pub fn set_target_speed(&self, mut speed: u16) -> io::Result<()> {
-        let max_speed = self.get_max_fan_speed()?;
-
-        if speed > max_speed {
-            speed = max_speed
+    // where Up/Down start from: the target if we can read it, else the actual speed
+    fn base_speed(&self) -> u16 {
+        match (&self.target, &self.cur_speed) {
+            (Ok(target), _) if *target > 0 => *target,
+            (_, Ok(speed)) => *speed,
+            _ => 0,
         }
-
-        let pattern = format!(
-            "/sys/devices/platform/hp-wmi/hwmon/hwmon*/fan{}_target",
-            self.id
-        );
-
-        let path = glob(&pattern)
-            .expect("failed to read glob pattern")
-            .filter_map(Result::ok)
-            .next()
-            .expect("no matching fan target file found");
-
-        let mut file = OpenOptions::new().write(true).open(path)?;
-        let data = format!("{}", speed);
-
-        file.write_all(data.as_bytes())?;
-
-        Ok(())
     }
+}
 
-    pub fn get_cur_speed(&self) -> u16 {
-        let pattern = format!(
-            "/sys/devices/platform/hp-wmi/hwmon/hwmon*/fan{}_input",
-            self.id
-        );
-
-        let path = glob(&pattern)
-            .expect("failed to read glob pattern")
-            .filter_map(Result::ok)
-            .next()
-            .expect("no matching fan input file found");
+// applies a curve editor key, returns the point to select next if it moved
+fn editor_key(
+    controller: &mut CurveController,
+    key_event: KeyEvent,
+    point: usize,
+    max: u16,
+) -> Option<usize> {
+    let curve = &mut controller.curve;
+    let (temp, value) = curve.points.get(point).copied()?;
+    let last = curve.points.len().saturating_sub(1);
 
-        let res_str = fs::read_to_string(&path)
-            .expect("failed to read fan input")
-            .trim()
-            .to_string();
+    let value_step = match curve.unit {
+        CurveUnit::Rpm => f64::from(FINE_STEP),
+        CurveUnit::Percent => 5.,
+    };
+    let shift = key_event.modifiers.contains(KeyModifiers::SHIFT);
 
-        if res_str.is_empty() {
-            panic!("fan {} returned empty speed", self.id);
+    match key_event.code {
+        KeyCode::Left if shift => return Some(curve.move_point(point, temp - 1., value, max)),
+        KeyCode::Right if shift => return Some(curve.move_point(point, temp + 1., value, max)),
+        KeyCode::Left => return Some(point.saturating_sub(1)),
+        KeyCode::Right => return Some((point + 1).min(last)),
+        KeyCode::Up => return Some(curve.move_point(point, temp, value + value_step, max)),
+        KeyCode::Down => return Some(curve.move_point(point, temp, value - value_step, max)),
+        KeyCode::Char('s') => {
+            curve.source = match curve.source {
+                CurveSource::Cpu => CurveSource::Gpu,
+                CurveSource::Gpu => CurveSource::Cpu,
+            };
+        }
+        KeyCode::Char('u') => {
+            let unit = match curve.unit {
+                CurveUnit::Rpm => CurveUnit::Percent,
+                CurveUnit::Percent => CurveUnit::Rpm,
+            };
+            curve.set_unit(unit, max);
+        }
+        KeyCode::Char('h') => curve.hysteresis = (curve.hysteresis - 1.).max(0.),
+        KeyCode::Char('H') => curve.hysteresis = (curve.hysteresis + 1.).min(20.),
+        KeyCode::Char('d') => {
+            curve.ramp_down_delay = curve.ramp_down_delay.saturating_sub(Duration::from_secs(1));
         }
+        KeyCode::Char('D') => {
+            curve.ramp_down_delay =
+                (curve.ramp_down_delay + Duration::from_secs(1)).min(Duration::from_secs(120));
+        }
+        _ => {}
+    }
+    None
+}
 
-        res_str
-            .parse()
-            .expect("failed to parse the output for get_cur_speed")
+fn rpm(value: &HwResult<u16>) -> String {
+    match value {
+        Ok(value) => format!("{} rpm", value),
+        Err(_) => "n/a".to_string(),
     }
 }
 
@@ -112,8 +155,20 @@ impl Widget for &Fan {
             .constraints(vec![Constraint::Fill(1), Constraint::Length(4)])
             .split(area);
 
+        let color = match self.cur_speed {
+            Ok(_) => Color::Blue,
+            Err(_) => Color::DarkGray,
+        };
+
+        let block = match self.selected {
+            true => Block::bordered()
+                .title(self.label.as_str().bold())
+                .border_style(Style::new().yellow()),
+            false => Block::bordered().title(self.label.as_str()),
+        };
+
         Canvas::default()
-            .block(Block::bordered().title(format!("{}", &self.id)))
+            .block(block)
             .x_bounds([-90.0, 90.0])
             .y_bounds([-90.0, 90.0])
             .paint(|ctx| {
@@ -121,51 +176,588 @@ impl Widget for &Fan {
                     x: 0.0,
                     y: 0.0,
                     radius: 75.0,
-                    color: Color::Blue,
+                    color,
                 });
                 ctx.draw(&Circle {
                     x: 0.0,
                     y: 0.0,
                     radius: 5.0,
-                    color: Color::Blue,
+                    color,
                 });
             })
             .render(layout[0], buf);
 
-        Paragraph::new(format!(
-            "Current Speed of fan_{} : {}",
-            self.id,
-            self.get_cur_speed()
-        ))
-        .render(layout[1], buf);
+        if self.cur_speed.is_err() {
+            Paragraph::new(format!("{} : sensor unavailable", self.label).red())
+                .render(layout[1], buf);
+            return;
+        }
+
+        let cols =
+            Layout::horizontal(vec![Constraint::Fill(1), Constraint::Fill(1)]).split(layout[1]);
+
+        Paragraph::new(vec![
+            Line::from("actual".dark_gray()),
+            Line::from(rpm(&self.cur_speed).bold()),
+        ])
+        .render(cols[0], buf);
+
+        Paragraph::new(vec![
+            Line::from("target".dark_gray()),
+            match self.pinned {
+                false => Line::from(rpm(&self.target).yellow().bold()),
+                true => Line::from(format!("{} (inactive)", rpm(&self.target)).dark_gray()),
+            },
+            Line::from(format!("max {}", rpm(&self.max)).dark_gray()),
+        ])
+        .render(cols[1], buf);
     }
 }
 
 pub struct FansWidget {
     pub title: &'static str,
+    backend: Backend,
+    logs: LogHandle,
+    // ticked by the sampler, only edited here
+    curves: CurvesHandle,
+    pub fans: Vec<Fan>,
+    pub mode: HwResult<FanMode>,
+
+    pub focused: bool,
+    pub selected: usize,
+    // digits typed so far for an exact target
+    pub input: Option<String>,
+    // selected point while the curve editor is open
+    pub editor: Option<usize>,
+
+    // latest temps for the curves, None if the sensor failed
+    cpu_temp: Option<f64>,
+    gpu_temp: Option<f64>,
 }
 
 impl FansWidget {
-    pub fn new(title: &'static str) -> Self {
-        Self { title: title }
+    pub fn new(
+        title: &'static str,
+        backend: Backend,
+        logs: LogHandle,
+        curves: CurvesHandle,
+    ) -> Self {
+        Self {
+            title,
+            // filled in from the first snapshot
+            fans: Vec::new(),
+            backend,
+            logs,
+            curves,
+            mode: Ok(FanMode::Auto),
+            focused: false,
+            selected: 0,
+            input: None,
+            editor: None,
+            cpu_temp: None,
+            gpu_temp: None,
+        }
+    }
+
+    pub fn update(&mut self, snapshot: &Snapshot) {
+        self.sync_fans(snapshot);
+
+        for fan in &mut self.fans {
+            fan.update(snapshot);
+        }
+        self.mode = snapshot.fan_mode.value.clone();
+        self.sync_mode();
+
+        self.cpu_temp = snapshot.curve_temp(CurveSource::Cpu);
+        self.gpu_temp = snapshot.curve_temp(CurveSource::Gpu);
+    }
+
+    fn source_temp(&self, source: CurveSource) -> Option<f64> {
+        match source {
+            CurveSource::Cpu => self.cpu_temp,
+            CurveSource::Gpu => self.gpu_temp,
+        }
+    }
+
+    // follows the backend's fan list, keeping curves of fans that stay
+    fn sync_fans(&mut self, snapshot: &Snapshot) {
+        let same = self.fans.len() == snapshot.fans.len()
+            && self
+                .fans
+                .iter()
+                .zip(&snapshot.fans)
+                .all(|(fan, reading)| fan.id == reading.id);
+        if same {
+            return;
+        }
+
+        let mut old = std::mem::take(&mut self.fans);
+        self.fans = snapshot
+            .fans
+            .iter()
+            .map(
+                |reading| match old.iter().position(|fan| fan.id == reading.id) {
+                    Some(idx) => old.swap_remove(idx),
+                    None => Fan::new(reading.id, reading.label.clone(), self.backend.clone()),
+                },
+            )
+            .collect();
+
+        self.editor = None;
+        self.select(self.selected.min(self.fans.len().saturating_sub(1)));
+    }
+
+    fn sync_mode(&mut self) {
+        let pinned = self.mode == Ok(FanMode::Max);
+        for fan in &mut self.fans {
+            fan.pinned = pinned;
+        }
+    }
+
+    pub fn set_mode(&mut self, mode: FanMode) {
+        match self.backend.set_fan_mode(mode) {
+            Ok(()) => {
+                self.mode = Ok(mode);
+                self.sync_mode();
+
+                // curves can only work through the target files
+                if mode == FanMode::Max && self.curves.disable_all() {
+                    self.logs
+                        .info("fans", format!("mode {}, curves disabled", mode));
+                } else {
+                    self.logs.info("fans", format!("mode {}", mode));
+                }
+            }
+            Err(err) => self.logs.error(
+                "fans",
+                format!("switching to {} mode failed: {}", mode, err),
+            ),
+        }
+    }
+
+    fn select(&mut self, idx: usize) {
+        self.selected = idx;
+        for (i, fan) in self.fans.iter_mut().enumerate() {
+            fan.selected = self.focused && i == idx;
+        }
+    }
+
+    fn set_target(&mut self, speed: u16) {
+        // the target file is ignored while pwm1_enable pins the fans
+        match &self.mode {
+            Ok(FanMode::Auto) => {}
+            Ok(mode) => {
+                self.logs.warn(
+                    "fans",
+                    format!(
+                        "not setting target in {} mode, press [a] for auto first",
+                        mode
+                    ),
+                );
+                return;
+            }
+            Err(err) => {
+                self.logs
+                    .warn("fans", format!("not setting target, mode unknown: {}", err));
+                return;
+            }
+        }
+
+        let Some(fan) = self.fans.get_mut(self.selected) else {
+            return;
+        };
+
+        // a hand picked target wins over the curve
+        let (was_enabled, written) = self
+            .curves
+            .take_over(fan.id, || fan.set_target_speed(speed));
+        let curve_note = match was_enabled {
+            true => ", curve disabled",
+            false => "",
+        };
+
+        match written {
+            Ok(written) if written < speed => self.logs.info(
+                "fans",
+                format!(
+                    "fan {}: target {} rpm (clamped from {}){}",
+                    fan.id, written, speed, curve_note
+                ),
+            ),
+            Ok(written) => self.logs.info(
+                "fans",
+                format!("fan {}: target {} rpm{}", fan.id, written, curve_note),
+            ),
+            Err(err) => self.logs.error(
+                "fans",
+                format!(
+                    "fan {}: setting target {} rpm failed: {}",
+                    fan.id, speed, err
+                ),
+            ),
+        }
+    }
+
+    fn step(&mut self, up: bool, step: u16) {
+        let Some(fan) = self.fans.get(self.selected) else {
+            return;
+        };
+        let base = fan.base_speed();
+        let speed = match up {
+            true => base.saturating_add(step),
+            false => base.saturating_sub(step),
+        };
+
+        self.set_target(speed);
+    }
+
+    fn handle_editor_key(&mut self, key_event: KeyEvent, point: usize) {
+        match key_event.code {
+            KeyCode::Char('e') => self.toggle_curve(),
+            KeyCode::Esc | KeyCode::Char('c') => self.editor = None,
+            _ => {
+                let Some(fan) = self.fans.get(self.selected) else {
+                    return;
+                };
+                let max = fan.max.clone().unwrap_or(0);
+                let next = self.curves.edit(fan.id, |controller| {
+                    editor_key(controller, key_event, point, max)
+                });
+                if let Some(next) = next {
+                    self.editor = Some(next);
+                }
+            }
+        }
+    }
+
+    fn toggle_curve(&mut self) {
+        let Some(fan) = self.fans.get(self.selected) else {
+            return;
+        };
+        let id = fan.id;
+        let enabled = self.curves.edit(id, |controller| {
+            controller.set_enabled(!controller.enabled);
+            controller.enabled
+        });
+
+        if !enabled {
+            self.logs
+                .info("fans", format!("fan {}: curve disabled", id));
+            return;
+        }
+
+        self.logs.info("fans", format!("fan {}: curve enabled", id));
+        if self.mode != Ok(FanMode::Auto) {
+            self.set_mode(FanMode::Auto);
+        }
+    }
+
+    fn handle_key(&mut self, key_event: KeyEvent) {
+        if let Some(point) = self.editor {
+            self.handle_editor_key(key_event, point);
+            return;
+        }
+
+        if let Some(input) = &mut self.input {
+            match key_event.code {
+                KeyCode::Char(c) if c.is_ascii_digit() && input.len() < 5 => input.push(c),
+                KeyCode::Backspace => {
+                    input.pop();
+                }
+                KeyCode::Enter => {
+                    let input = self.input.take().unwrap_or_default();
+                    match input.parse::<u16>() {
+                        Ok(speed) => self.set_target(speed),
+                        Err(_) => self
+                            .logs
+                            .warn("fans", format!("{:?} is not a valid rpm", input)),
+                    }
+                }
+                KeyCode::Esc => self.input = None,
+                _ => {}
+            }
+            return;
+        }
+
+        match key_event.code {
+            KeyCode::Left => self.select(self.selected.saturating_sub(1)),
+            KeyCode::Right => self.select((self.selected + 1).min(self.fans.len().max(1) - 1)),
+            KeyCode::Up => self.step(true, FINE_STEP),
+            KeyCode::Down => self.step(false, FINE_STEP),
+            KeyCode::PageUp => self.step(true, COARSE_STEP),
+            KeyCode::PageDown => self.step(false, COARSE_STEP),
+            KeyCode::Enter => self.input = Some(String::new()),
+            KeyCode::Char('a') => self.set_mode(FanMode::Auto),
+            KeyCode::Char('x') => self.set_mode(FanMode::Max),
+            KeyCode::Char('c') if !self.fans.is_empty() => self.editor = Some(0),
+            _ => {}
+        }
+    }
+}
+
+impl Panel for FansWidget {
+    fn set_focused(&mut self, focused: bool) {
+        self.focused = focused;
+        self.input = None;
+        self.select(self.selected);
+    }
+
+    fn captures_keys(&self) -> bool {
+        self.input.is_some() || self.editor.is_some()
+    }
+
+    fn handle_key_event(&mut self, key_event: KeyEvent) -> Vec<Action> {
+        self.handle_key(key_event);
+        Vec::new()
     }
 }
 
 impl Widget for &FansWidget {
     fn render(self, area: ratatui::prelude::Rect, buf: &mut Buffer) {
-        Block::default()
+        let mode = match &self.mode {
+            Ok(FanMode::Max) => "max".red().bold(),
+            Ok(FanMode::Auto) => "auto (BIOS)".green(),
+            Err(_) => "unknown".dark_gray(),
+        };
+
+        let mut block = Block::default()
             .borders(Borders::ALL)
             .border_type(BorderType::Rounded)
-            .render(area, buf);
+            .title(Line::from(vec![
+                self.title.into(),
+                " mode: ".dark_gray(),
+                mode,
+                " ".into(),
+            ]));
 
-        let fan1 = Fan::new(1);
-        let fan2 = Fan::new(2);
+        if self.focused {
+            let hint = match &self.input {
+                Some(input) => format!(" target: {}_  [Enter] set  [Esc] cancel ", input),
+                None if self.mode == Ok(FanMode::Auto) => {
+                    " [←/→] fan  [↑/↓] ±100  [PgUp/PgDn] ±500  [Enter] exact  [x] max  [c] curve "
+                        .to_string()
+                }
+                None => " [a] auto  [x] max  [c] curve ".to_string(),
+            };
 
-        let vis_area_lay = Layout::horizontal(vec![Constraint::Fill(1), Constraint::Fill(1)])
+            block = block
+                .border_style(Style::new().yellow())
+                .title_bottom(Line::from(hint).centered());
+        } else {
+            block = block.title_bottom(Line::from(" [f] control fans ".dark_gray()).centered());
+        }
+
+        block.render(area, buf);
+
+        if let (Some(point), Some(fan)) = (self.editor, self.fans.get(self.selected)) {
+            let controller = self.curves.get(fan.id);
+            CurveEditor {
+                fan_id: fan.id,
+                controller: &controller,
+                max: fan.max.clone().unwrap_or(0),
+                selected: point,
+                live: self.source_temp(controller.curve.source),
+            }
+            .render(area.inner(margin!(1, 1)), buf);
+            return;
+        }
+
+        if self.fans.is_empty() {
+            Paragraph::new("no fans found".dark_gray())
+                .centered()
+                .render(area.inner(margin!(1, area.height / 2)), buf);
+            return;
+        }
+
+        // up to FANS_PER_ROW side by side, then wrap
+        let rows = self.fans.len().div_ceil(FANS_PER_ROW);
+        let row_areas = Layout::vertical(vec![Constraint::Fill(1); rows])
             .margin(1)
             .split(area);
 
-        fan1.render(vis_area_lay[0], buf);
-        fan2.render(vis_area_lay[1], buf);
+        for (fans, row_area) in self.fans.chunks(FANS_PER_ROW).zip(row_areas.iter()) {
+            let fan_areas =
+                Layout::horizontal(vec![Constraint::Fill(1); fans.len()]).split(*row_area);
+
+            for (fan, fan_area) in fans.iter().zip(fan_areas.iter()) {
+                fan.render(*fan_area, buf);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{sync::Arc, time::Instant};
+
+    use ratatui::layout::Rect;
+
+    use super::*;
+    use crate::{
+        hardware::{HardwareBackend, error::HwError, mock::MockBackend},
+        sampler::{FanReading, Sample},
+    };
+
+    // what the sampler would have read from `mock`
+    fn snapshot(mock: &MockBackend) -> Snapshot {
+        let fans = mock.fans().unwrap();
+        Snapshot {
+            fans: fans
+                .into_iter()
+                .map(|fan| FanReading {
+                    id: fan.id,
+                    label: fan.label,
+                    speed: mock.fan_speed(fan.id),
+                    target: mock.fan_target(fan.id),
+                    max: mock.fan_max(fan.id),
+                })
+                .collect(),
+            fan_mode: Sample::new(mock.fan_mode()),
+            ..Snapshot::default()
+        }
+    }
+
+    fn widget(mode: FanMode) -> (Arc<MockBackend>, FansWidget, LogHandle) {
+        let mock = Arc::new(MockBackend::new().fan(1, 5800, vec![2600]));
+        mock.set_fan_mode(mode).unwrap();
+        mock.set_fan_target(1, 5600).unwrap();
+
+        let logs = LogHandle::new();
+        let mut widget = FansWidget::new("Fans", mock.clone(), logs.clone(), CurvesHandle::new());
+        widget.update(&snapshot(&mock));
+        widget.set_focused(true);
+        (mock, widget, logs)
+    }
+
+    fn press(widget: &mut FansWidget, keys: &[KeyCode]) {
+        for key in keys {
+            widget.handle_key_event(KeyEvent::from(*key));
+        }
+    }
+
+    fn last_log(logs: &LogHandle) -> String {
+        logs.read(|buffer| buffer.entries().back().unwrap().message.clone())
+    }
+
+    fn text(buf: &Buffer) -> String {
+        buf.content().iter().map(|cell| cell.symbol()).collect()
+    }
+
+    #[test]
+    fn steps_are_clamped_to_fan_max() {
+        let (mock, mut widget, logs) = widget(FanMode::Auto);
+
+        press(&mut widget, &[KeyCode::PageUp]);
+        assert_eq!(mock.fan_target(1), Ok(5800));
+        assert_eq!(
+            last_log(&logs),
+            "fan 1: target 5800 rpm (clamped from 6100)"
+        );
+
+        press(&mut widget, &[KeyCode::Down]);
+        assert_eq!(mock.fan_target(1), Ok(5700));
+    }
+
+    #[test]
+    fn typed_targets_are_clamped_to_fan_max() {
+        let (mock, mut widget, _) = widget(FanMode::Auto);
+
+        press(
+            &mut widget,
+            &[
+                KeyCode::Enter,
+                KeyCode::Char('9'),
+                KeyCode::Char('0'),
+                KeyCode::Char('0'),
+                KeyCode::Char('0'),
+                KeyCode::Enter,
+            ],
+        );
+        assert_eq!(mock.fan_target(1), Ok(5800));
+    }
+
+    #[test]
+    fn targets_are_refused_at_max() {
+        let (mock, mut widget, logs) = widget(FanMode::Max);
+
+        press(&mut widget, &[KeyCode::Up]);
+        assert_eq!(mock.fan_target(1), Ok(5600));
+        assert!(last_log(&logs).starts_with("not setting target in max mode"));
+    }
+
+    #[test]
+    fn renders_speeds_and_mode() {
+        let (_, widget, _) = widget(FanMode::Auto);
+
+        let area = Rect::new(0, 0, 60, 20);
+        let mut buf = Buffer::empty(area);
+        (&widget).render(area, &mut buf);
+        let text = text(&buf);
+
+        assert!(text.contains("mode: auto (BIOS)"));
+        assert!(text.contains("fan 1"));
+        assert!(text.contains("2600 rpm"));
+        assert!(text.contains("5600 rpm"));
+        assert!(text.contains("max 5800 rpm"));
+    }
+
+    #[test]
+    fn renders_unavailable_fans() {
+        let mock = Arc::new(
+            MockBackend::new()
+                .fan(1, 5800, vec![2600])
+                .failing("fan_speed", HwError::missing("mock fan_speed")),
+        );
+        let mut widget =
+            FansWidget::new("Fans", mock.clone(), LogHandle::new(), CurvesHandle::new());
+        widget.update(&snapshot(&mock));
+
+        let area = Rect::new(0, 0, 60, 20);
+        let mut buf = Buffer::empty(area);
+        (&widget).render(area, &mut buf);
+
+        assert!(text(&buf).contains("fan 1 : sensor unavailable"));
+    }
+
+    #[test]
+    fn curves_stop_at_max() {
+        let (mock, mut widget, _) = widget(FanMode::Auto);
+        press(&mut widget, &[KeyCode::Char('c'), KeyCode::Char('e')]);
+
+        let backend: Backend = mock.clone();
+        let mut hot = Snapshot {
+            cpu_temp: Sample::new(Ok(60)),
+            ..snapshot(&mock)
+        };
+        // 42.5% of 5800 at 60ºC
+        widget.curves.run(&backend, &hot, Instant::now());
+        assert_eq!(mock.fan_target(1), Ok(2465));
+
+        // the widget turns them off when the fans get pinned
+        press(&mut widget, &[KeyCode::Esc, KeyCode::Char('x')]);
+        hot.cpu_temp = Sample::new(Ok(85));
+        hot.fan_mode = Sample::new(mock.fan_mode());
+        widget.curves.run(&backend, &hot, Instant::now());
+        assert_eq!(mock.fan_target(1), Ok(2465));
+        assert!(!widget.curves.get(1).enabled);
+    }
+
+    #[test]
+    fn editor_survives_an_empty_curve() {
+        let (_, mut widget, _) = widget(FanMode::Auto);
+        widget
+            .curves
+            .edit(1, |controller| controller.curve.points.clear());
+
+        press(
+            &mut widget,
+            &[KeyCode::Char('c'), KeyCode::Right, KeyCode::Up],
+        );
+        assert_eq!(widget.editor, Some(0));
+
+        let area = Rect::new(0, 0, 80, 24);
+        let mut buf = Buffer::empty(area);
+        (&widget).render(area, &mut buf);
+        assert!(text(&buf).contains("fan 1 curve"));
     }
 }
@@ -1,45 +1,527 @@
+use std::cell::Cell;
+
+use crossterm::event::{KeyCode, KeyEvent};
 use ratatui::{
     buffer::Buffer,
-    layout::Alignment,
-    text::Line,
-    widgets::{Block, BorderType, Borders, Paragraph, Widget, Wrap},
+    layout::{Constraint, Layout},
+    style::{Color, Style, Stylize},
+    text::{Line, Span},
+    widgets::{Block, BorderType, Borders, Paragraph, Widget},
+};
+
+use crate::{
+    logs::{Level, LogBuffer, LogEntry, LogFilter, LogHandle, find_matches},
+    widgets::panel::{Action, Panel},
 };
 
-pub struct Logs<'a> {
+fn level_style(level: Level) -> Style {
+    match level {
+        Level::Debug => Style::new().fg(Color::DarkGray),
+        Level::Info => Style::new().fg(Color::Green),
+        Level::Warn => Style::new().fg(Color::Yellow),
+        Level::Error => Style::new().fg(Color::Red).bold(),
+    }
+}
+
+// `current` is the match n/N last jumped to
+fn entry_line(entry: &LogEntry, search: &str, current: bool) -> Line<'static> {
+    let style = match entry.level {
+        Level::Debug => Style::new().dark_gray(),
+        Level::Error => Style::new().red(),
+        _ => Style::new(),
+    };
+    let highlight = match current {
+        true => Style::new().black().on_cyan(),
+        false => Style::new().black().on_yellow(),
+    };
+
+    let mut spans = vec![
+        entry.clock().dark_gray(),
+        " ".into(),
+        Span::styled(format!("{:<5}", entry.level), level_style(entry.level)),
+        " ".into(),
+        Span::from(format!("{:<9}", entry.source)).cyan(),
+        " ".into(),
+    ];
+
+    let message = &entry.message;
+    let mut from = 0;
+    for range in find_matches(message, search) {
+        spans.push(Span::styled(message[from..range.start].to_string(), style));
+        spans.push(Span::styled(message[range.clone()].to_string(), highlight));
+        from = range.end;
+    }
+    spans.push(Span::styled(message[from..].to_string(), style));
+
+    Line::from(spans)
+}
+
+// One row per entry, newest at the bottom. Follows the tail until scrolled
+// up, End (or scrolling back down to the bottom) follows again.
+pub struct Logs {
     pub title: &'static str,
+    pub focused: bool,
+    logs: LogHandle,
+
+    filter: LogFilter,
+    // the query, matches are highlighted and n / N jump between them
+    search: String,
+    // true while the query is being typed
+    typing: bool,
+    // match n/N last jumped to
+    current: Option<u64>,
 
-    pub y_scroll: u16,
-    pub logs: Vec<Line<'a>>,
+    // absolute position (see LogBuffer::dropped) of the top row while not
+    // following, so rows stay put while new entries come in
+    top: Option<u64>,
+    // rows the last render had room for, for PageUp / PageDown
+    height: Cell<usize>,
 }
 
-impl Logs<'_> {
-    pub fn new(title: &'static str) -> Self {
+impl Logs {
+    pub fn new(title: &'static str, logs: LogHandle) -> Self {
         Self {
-            title: title,
-            y_scroll: 0,
-            logs: vec![Line::from("Hello World!")],
+            title,
+            focused: false,
+            logs,
+            filter: LogFilter::default(),
+            search: String::new(),
+            typing: false,
+            current: None,
+            top: None,
+            height: Cell::new(10),
         }
     }
 
-    pub fn push_logs(&mut self, log: &str) {
-        self.logs.push(Line::from(log));
+    pub fn following(&self) -> bool {
+        self.top.is_none()
+    }
+
+    // absolute positions of the entries that pass the filter
+    fn rows(&self, buffer: &LogBuffer) -> Vec<u64> {
+        buffer
+            .positioned()
+            .filter(|(_, entry)| self.filter.matches(entry))
+            .map(|(pos, _)| pos)
+            .collect()
+    }
+
+    // index into `rows` of the top row, and of the top row when following
+    fn view(&self, rows: &[u64]) -> (usize, usize) {
+        let tail = rows.len().saturating_sub(self.height.get());
+        let start = match self.top {
+            Some(top) => rows.partition_point(|pos| *pos < top).min(tail),
+            None => tail,
+        };
+        (start, tail)
+    }
+
+    fn set_start(&mut self, rows: &[u64], start: usize, tail: usize) {
+        self.top = (start < tail).then(|| rows[start]);
+    }
+
+    // moves the view by `rows`, negative is up
+    fn scroll(&mut self, by: i64) {
+        let rows = self.logs.read(|buffer| self.rows(buffer));
+        let (start, tail) = self.view(&rows);
+        let start = start.saturating_add_signed(by as isize).min(tail);
+        self.set_start(&rows, start, tail);
+    }
+
+    fn scroll_to_top(&mut self) {
+        let rows = self.logs.read(|buffer| self.rows(buffer));
+        let (_, tail) = self.view(&rows);
+        self.set_start(&rows, 0, tail);
+    }
+
+    // jumps to the next match below (`forward`) or above the last one,
+    // wrapping around, and scrolls it into view
+    fn jump(&mut self, forward: bool) {
+        let (rows, matches) = self.logs.read(|buffer| {
+            let rows = self.rows(buffer);
+            let matches: Vec<u64> = buffer
+                .positioned()
+                .filter(|(pos, entry)| {
+                    rows.binary_search(pos).is_ok()
+                        && !find_matches(&entry.message, &self.search).is_empty()
+                })
+                .map(|(pos, _)| pos)
+                .collect();
+            (rows, matches)
+        });
+        if matches.is_empty() {
+            self.current = None;
+            return;
+        }
+
+        // from the last match, or from what's on screen
+        let (start, tail) = self.view(&rows);
+        let top = rows.get(start).copied().unwrap_or(0);
+        let bottom = rows
+            .get(
+                (start + self.height.get())
+                    .min(rows.len())
+                    .saturating_sub(1),
+            )
+            .copied()
+            .unwrap_or(0);
+
+        let next = match (forward, self.current) {
+            (true, Some(from)) => matches.iter().find(|pos| **pos > from),
+            (true, None) => matches.iter().find(|pos| **pos >= top),
+            (false, Some(from)) => matches.iter().rev().find(|pos| **pos < from),
+            (false, None) => matches.iter().rev().find(|pos| **pos <= bottom),
+        };
+        let wrapped = match forward {
+            true => matches.first(),
+            false => matches.last(),
+        };
+        let Some(&next) = next.or(wrapped) else {
+            return;
+        };
+        self.current = Some(next);
+
+        // only move when it's off screen, and then put it in the middle
+        let idx = rows.partition_point(|pos| *pos < next);
+        let height = self.height.get().max(1);
+        if idx < start || idx >= start + height {
+            let start = idx.saturating_sub(height / 2).min(tail);
+            self.set_start(&rows, start, tail);
+        }
+    }
+
+    // incremental search: the newest match at or above the bottom of the view
+    fn search_changed(&mut self) {
+        self.current = None;
+        if !self.search.is_empty() {
+            self.jump(false);
+        }
+    }
+
+    fn clear_search(&mut self) {
+        self.search.clear();
+        self.typing = false;
+        self.current = None;
+    }
+
+    fn cycle_source(&mut self) {
+        let sources = self.logs.read(|buffer| buffer.sources());
+        // all -> each source seen so far -> all
+        self.filter.source = match self.filter.source {
+            None => sources.first().copied(),
+            Some(source) => sources
+                .iter()
+                .position(|s| *s == source)
+                .and_then(|idx| sources.get(idx + 1))
+                .copied(),
+        };
+    }
+
+    fn handle_search_key(&mut self, key_event: KeyEvent) {
+        match key_event.code {
+            KeyCode::Char(c) => {
+                self.search.push(c);
+                self.search_changed();
+            }
+            KeyCode::Backspace => {
+                self.search.pop();
+                self.search_changed();
+            }
+            KeyCode::Enter => self.typing = false,
+            KeyCode::Esc => self.clear_search(),
+            _ => {}
+        }
     }
 }
 
-impl Widget for &Logs<'_> {
+impl Panel for Logs {
+    fn set_focused(&mut self, focused: bool) {
+        self.focused = focused;
+        self.typing = false;
+    }
+
+    fn captures_keys(&self) -> bool {
+        self.typing
+    }
+
+    fn handle_key_event(&mut self, key_event: KeyEvent) -> Vec<Action> {
+        if self.typing {
+            self.handle_search_key(key_event);
+            return Vec::new();
+        }
+
+        let page = self.height.get().max(1) as i64;
+
+        match key_event.code {
+            KeyCode::Up => self.scroll(-1),
+            KeyCode::Down => self.scroll(1),
+            KeyCode::PageUp => self.scroll(-page),
+            KeyCode::PageDown => self.scroll(page),
+            KeyCode::Home => self.scroll_to_top(),
+            KeyCode::End => self.top = None,
+            KeyCode::Char('l') => {
+                self.filter.min_level = match self.filter.min_level.map(Level::next) {
+                    // wrapped around
+                    Some(Level::Debug) => None,
+                    None => Some(Level::Info),
+                    level => level,
+                };
+            }
+            KeyCode::Char('s') => self.cycle_source(),
+            KeyCode::Char('/') => {
+                self.search.clear();
+                self.typing = true;
+                self.current = None;
+            }
+            KeyCode::Char('n') => self.jump(true),
+            KeyCode::Char('N') => self.jump(false),
+            KeyCode::Esc => self.clear_search(),
+            _ => {}
+        }
+
+        Vec::new()
+    }
+}
+
+impl Widget for &Logs {
     fn render(self, area: ratatui::prelude::Rect, buf: &mut Buffer) {
-        let block = Block::default()
+        let mut status = Vec::new();
+        if let Some(level) = self.filter.min_level {
+            status.push(format!(" {}+ ", level).yellow());
+        }
+        if let Some(source) = self.filter.source {
+            status.push(format!(" {} ", source).cyan());
+        }
+        if !self.following() {
+            status.push(" paused, [End] follow ".yellow());
+        }
+
+        let mut block = Block::default()
             .borders(Borders::ALL)
             .border_type(BorderType::Rounded)
-            .title(self.title);
+            .title(self.title)
+            .title(Line::from(status).right_aligned());
+        if self.focused {
+            block = block.border_style(Style::new().yellow()).title_bottom(
+                Line::from(
+                    " [↑/↓] [PgUp/PgDn] [Home/End] scroll  [l] level  [s] source  [/] search  [n/N] next/prev ",
+                )
+                .centered(),
+            );
+        }
+
+        let inner = block.inner(area);
+        block.render(area, buf);
+
+        // the query gets the last line while there is one
+        let searching = self.typing || !self.search.is_empty();
+        let [list_area, search_area] =
+            Layout::vertical([Constraint::Fill(1), Constraint::Length(searching as u16)])
+                .areas(inner);
+
+        let height = list_area.height as usize;
+        self.height.set(height);
+
+        let (lines, count): (Vec<Line>, usize) = self.logs.read(|buffer| {
+            let rows = self.rows(buffer);
+            let (start, _) = self.view(&rows);
+
+            let visible = rows.iter().skip(start).take(height);
+            let lines = visible
+                .filter_map(|pos| {
+                    let entry = buffer.entries().get((pos - buffer.dropped()) as usize)?;
+                    Some(entry_line(entry, &self.search, self.current == Some(*pos)))
+                })
+                .collect();
+
+            let count = match self.search.is_empty() {
+                true => 0,
+                false => rows
+                    .iter()
+                    .filter_map(|pos| buffer.entries().get((pos - buffer.dropped()) as usize))
+                    .map(|entry| find_matches(&entry.message, &self.search).len())
+                    .sum(),
+            };
+            (lines, count)
+        });
+
+        if lines.is_empty() && self.filter != LogFilter::default() {
+            Paragraph::new("no entries match the filter, [l] / [s] to change it".dark_gray())
+                .centered()
+                .render(list_area, buf);
+        } else {
+            Paragraph::new(lines).render(list_area, buf);
+        }
+
+        if searching {
+            let mut prompt = vec!["/".yellow(), self.search.clone().into()];
+            if self.typing {
+                prompt.push("█".into());
+            }
+            prompt.push(format!("  {} matches", count).dark_gray());
+            if !self.typing {
+                prompt.push("  [Esc] clear".dark_gray());
+            }
+            Line::from(prompt).render(search_area, buf);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::logs::CAPACITY;
+
+    fn press(widget: &mut Logs, keys: &[KeyCode]) {
+        for key in keys {
+            widget.handle_key_event(KeyEvent::from(*key));
+        }
+    }
+
+    // messages of the rows that pass the filter
+    fn shown(widget: &Logs) -> Vec<String> {
+        widget.logs.read(|buffer| {
+            widget
+                .rows(buffer)
+                .into_iter()
+                .map(|pos| {
+                    buffer.entries()[(pos - buffer.dropped()) as usize]
+                        .message
+                        .clone()
+                })
+                .collect()
+        })
+    }
+
+    // absolute position of the top row on screen
+    fn top_row(widget: &Logs) -> u64 {
+        widget.logs.read(|buffer| {
+            let rows = widget.rows(buffer);
+            rows[widget.view(&rows).0]
+        })
+    }
+
+    #[test]
+    fn filters_cycle() {
+        let logs = LogHandle::new();
+        logs.debug("fans", "spinning");
+        logs.info("gpu", "awake");
+        logs.warn("power", "on battery");
+        logs.error("fans", "write failed");
+        let mut widget = Logs::new("Logs", logs);
+
+        let mut levels = vec![shown(&widget).len()];
+        for _ in 0..4 {
+            press(&mut widget, &[KeyCode::Char('l')]);
+            levels.push(shown(&widget).len());
+        }
+        // all, info+, warn+, error, all again
+        assert_eq!(levels, [4, 3, 2, 1, 4]);
+
+        let mut sources = vec![widget.filter.source];
+        for _ in 0..4 {
+            press(&mut widget, &[KeyCode::Char('s')]);
+            sources.push(widget.filter.source);
+        }
+        assert_eq!(
+            sources,
+            [None, Some("fans"), Some("gpu"), Some("power"), None]
+        );
+
+        press(&mut widget, &[KeyCode::Char('s')]);
+        assert_eq!(shown(&widget), ["spinning", "write failed"]);
+    }
+
+    #[test]
+    fn search_jumps_wrap_around() {
+        let logs = LogHandle::new();
+        for message in [
+            "fan 1 up",
+            "gpu awake",
+            "fan 2 up",
+            "gpu asleep",
+            "FAN 1 down",
+        ] {
+            logs.info("app", message);
+        }
+        let mut widget = Logs::new("Logs", logs);
+
+        press(
+            &mut widget,
+            &[
+                KeyCode::Char('/'),
+                KeyCode::Char('f'),
+                KeyCode::Char('a'),
+                KeyCode::Char('n'),
+                KeyCode::Enter,
+            ],
+        );
+        // typing jumps to the newest match
+        assert!(!widget.captures_keys());
+        assert_eq!(widget.current, Some(4));
+
+        let mut jumps = Vec::new();
+        for key in [KeyCode::Char('n'), KeyCode::Char('n'), KeyCode::Char('n')] {
+            press(&mut widget, &[key]);
+            jumps.push(widget.current.unwrap());
+        }
+        assert_eq!(jumps, [0, 2, 4]);
+
+        press(&mut widget, &[KeyCode::Char('n'), KeyCode::Char('N')]);
+        assert_eq!(widget.current, Some(4));
+        press(&mut widget, &[KeyCode::Char('N'), KeyCode::Char('N')]);
+        assert_eq!(widget.current, Some(0));
+
+        press(&mut widget, &[KeyCode::Esc]);
+        assert!(widget.search.is_empty());
+        assert_eq!(widget.current, None);
+        // nothing to jump to any more
+        press(&mut widget, &[KeyCode::Char('n')]);
+        assert_eq!(widget.current, None);
+    }
+
+    #[test]
+    fn escape_while_typing_clears_the_query() {
+        let mut widget = Logs::new("Logs", LogHandle::new());
+        press(&mut widget, &[KeyCode::Char('/'), KeyCode::Char('g')]);
+        assert!(widget.captures_keys());
+        assert_eq!(widget.search, "g");
+
+        press(&mut widget, &[KeyCode::Esc]);
+        assert!(!widget.captures_keys());
+        assert!(widget.search.is_empty());
+    }
+
+    #[test]
+    fn scrolling_up_pauses_until_back_at_the_bottom() {
+        let logs = LogHandle::new();
+        for idx in 0..20 {
+            logs.info("app", format!("entry {}", idx));
+        }
+        let mut widget = Logs::new("Logs", logs.clone());
+        assert!(widget.following());
+
+        press(&mut widget, &[KeyCode::Up]);
+        assert!(!widget.following());
+        assert_eq!(top_row(&widget), 9);
+
+        // rows stay put while entries come in
+        logs.info("app", "entry 20");
+        assert_eq!(top_row(&widget), 9);
+
+        // and when the top row falls off, the view holds the oldest left
+        for idx in 21..CAPACITY + 30 {
+            logs.info("app", format!("entry {}", idx));
+        }
+        assert!(!widget.following());
+        let oldest = logs.read(|buffer| buffer.dropped());
+        assert_eq!(top_row(&widget), oldest);
 
-        let logs = self.logs[..].to_vec();
+        press(&mut widget, &[KeyCode::End]);
+        assert!(widget.following());
 
-        Paragraph::new(logs)
-            .block(block)
-            .alignment(Alignment::Left)
-            .wrap(Wrap { trim: true })
-            .scroll((self.y_scroll, 0))
-            .render(area, buf);
+        // scrolling back down to the bottom follows again too
+        press(&mut widget, &[KeyCode::PageUp, KeyCode::PageDown]);
+        assert!(widget.following());
     }
 }
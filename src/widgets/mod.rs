@@ -1,6 +1,12 @@
-pub mod counter;
+pub mod battery_widget;
 pub mod cpu_cores_widget;
+pub mod cpu_power_widget;
+pub mod fan_curve_widget;
 pub mod fans_widget;
 pub mod logs_widget;
 pub mod navbar;
+pub mod panel;
+pub mod processes_widget;
+pub mod sensors_widget;
+pub mod settings_widget;
 pub mod usage_widget;
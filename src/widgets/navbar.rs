@@ -1,17 +1,172 @@
 use ratatui::{
     buffer::Buffer,
+    layout::{Constraint, Layout, Position, Rect},
     style::Stylize,
-    text::Line,
+    text::{Line, Span},
     widgets::{Block, BorderType, Borders, Paragraph, Widget},
 };
 
+use crate::{
+    hardware::{Backend, error::HwResult},
+    logs::LogHandle,
+    margin,
+    sampler::Snapshot,
+};
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Page {
+    #[default]
+    Dashboard,
+    Fans,
+    Thermals,
+    Power,
+    Processes,
+    Logs,
+    Settings,
+}
+
+// in tab order, number keys are 1-based indices into this
+pub const PAGES: [Page; 7] = [
+    Page::Dashboard,
+    Page::Fans,
+    Page::Thermals,
+    Page::Power,
+    Page::Processes,
+    Page::Logs,
+    Page::Settings,
+];
+
+impl Page {
+    pub fn title(self) -> &'static str {
+        match self {
+            Page::Dashboard => "Dashboard",
+            Page::Fans => "Fans",
+            Page::Thermals => "Thermals",
+            Page::Power => "Power",
+            Page::Processes => "Processes",
+            Page::Logs => "Logs",
+            Page::Settings => "Settings",
+        }
+    }
+
+    fn index(self) -> usize {
+        PAGES.iter().position(|page| *page == self).unwrap_or(0)
+    }
+
+    // '1' is the first page
+    pub fn from_key(key: char) -> Option<Page> {
+        let idx = key.to_digit(10)?.checked_sub(1)?;
+        PAGES.get(idx as usize).copied()
+    }
+
+    pub fn next(self) -> Page {
+        PAGES[(self.index() + 1) % PAGES.len()]
+    }
+
+    pub fn prev(self) -> Page {
+        PAGES[(self.index() + PAGES.len() - 1) % PAGES.len()]
+    }
+}
+
+// Page tabs on the left, platform profile on the right.
 pub struct Navbar {
-    pub title: &'static str,
+    backend: Backend,
+    logs: LogHandle,
+    pub page: Page,
+
+    pub profile: HwResult<String>,
+    pub profile_choices: Vec<String>,
 }
 
 impl Navbar {
-    pub fn new(title: &'static str) -> Self {
-        Self { title: title }
+    pub fn new(backend: Backend, logs: LogHandle) -> Self {
+        Self {
+            backend,
+            logs,
+            page: Page::default(),
+            profile: Ok(String::new()),
+            profile_choices: Vec::new(),
+        }
+    }
+
+    pub fn update(&mut self, snapshot: &Snapshot) {
+        self.profile = snapshot.platform_profile.value.clone();
+        self.profile_choices = snapshot
+            .platform_profile_choices
+            .value
+            .clone()
+            .unwrap_or_default();
+    }
+
+    // tabs on the left, the profile gets whatever they leave over
+    fn split(area: Rect) -> [Rect; 2] {
+        let tabs: usize = PAGES.iter().map(|page| Self::tab_label(*page).len()).sum();
+        Layout::horizontal([Constraint::Length(tabs as u16), Constraint::Fill(1)])
+            .areas(area.inner(margin!(1, 1)))
+    }
+
+    // where each tab ends up when the navbar is drawn in `area`, cut off
+    // tabs are clipped so clicks only land on what's visible
+    fn tab_areas(area: Rect) -> Vec<(Page, Rect)> {
+        let [tabs, _] = Self::split(area);
+        let mut x = tabs.x;
+
+        PAGES
+            .iter()
+            .map(|page| {
+                let width = Self::tab_label(*page).len() as u16;
+                let tab = Rect::new(x, tabs.y, width, 1).intersection(tabs);
+                x = x.saturating_add(width);
+                (*page, tab)
+            })
+            .collect()
+    }
+
+    fn tab_label(page: Page) -> String {
+        format!(" {} {} ", page.index() + 1, page.title())
+    }
+
+    // the tab under a mouse click, `area` being where the navbar was drawn
+    pub fn tab_at(area: Rect, column: u16, row: u16) -> Option<Page> {
+        Self::tab_areas(area)
+            .into_iter()
+            .find(|(_, tab)| tab.contains(Position::new(column, row)))
+            .map(|(page, _)| page)
+    }
+
+    // switches to the next advertised profile
+    pub fn cycle_profile(&mut self) {
+        let current = match &self.profile {
+            Ok(profile) => profile.clone(),
+            Err(err) => {
+                self.logs.warn("profile", format!("unavailable: {}", err));
+                return;
+            }
+        };
+
+        if self.profile_choices.is_empty() {
+            self.logs.warn("profile", "no choices advertised");
+            return;
+        }
+
+        let next = self
+            .profile_choices
+            .iter()
+            .position(|choice| *choice == current)
+            .map(|idx| (idx + 1) % self.profile_choices.len())
+            .unwrap_or(0);
+        let next = self.profile_choices[next].clone();
+
+        match self.backend.set_platform_profile(&next) {
+            Ok(()) => {
+                self.logs
+                    .info("profile", format!("{} -> {}", current, next));
+                self.profile = Ok(next);
+            }
+            Err(err) => self
+                .logs
+                .error("profile", format!("switching to {} failed: {}", next, err)),
+        }
     }
 }
 
@@ -19,8 +174,88 @@ impl Widget for &Navbar {
     fn render(self, area: ratatui::prelude::Rect, buf: &mut Buffer) {
         Block::default()
             .borders(Borders::ALL)
-            .title(self.title)
             .border_type(BorderType::Rounded)
             .render(area, buf);
+
+        for (page, tab) in Navbar::tab_areas(area) {
+            let label = Navbar::tab_label(page);
+            let label = match page == self.page {
+                true => label.black().on_cyan().bold(),
+                false => label.into(),
+            };
+            Line::from(label).render(tab, buf);
+        }
+
+        let [_, profile_area] = Navbar::split(area);
+
+        let mut spans: Vec<Span> = vec!["profile: ".dark_gray()];
+        match &self.profile {
+            Ok(profile) => {
+                for choice in &self.profile_choices {
+                    if choice == profile {
+                        spans.push(format!("[{}]", choice).yellow().bold());
+                    } else {
+                        spans.push(choice.clone().dark_gray());
+                    }
+                    spans.push(" ".into());
+                }
+                spans.push(" [p] cycle".dark_gray());
+            }
+            Err(_) => spans.push("unavailable".dark_gray()),
+        }
+
+        Paragraph::new(Line::from(spans))
+            .right_aligned()
+            .render(profile_area, buf);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pages_cycle_both_ways() {
+        assert_eq!(Page::Dashboard.next(), Page::Fans);
+        assert_eq!(Page::Settings.next(), Page::Dashboard);
+        assert_eq!(Page::Dashboard.prev(), Page::Settings);
+        assert!(PAGES.iter().all(|page| page.next().prev() == *page));
+    }
+
+    #[test]
+    fn number_keys_are_one_based() {
+        assert_eq!(Page::from_key('1'), Some(Page::Dashboard));
+        assert_eq!(Page::from_key('7'), Some(Page::Settings));
+        assert_eq!(Page::from_key('0'), None);
+        assert_eq!(Page::from_key('8'), None);
+    }
+
+    #[test]
+    fn the_profile_never_covers_tabs() {
+        let area = Rect::new(0, 0, 90, 3);
+        let [tabs, profile] = Navbar::split(area);
+        assert!(!tabs.intersects(profile));
+
+        // the last tab, right where it's drawn
+        let (page, settings) = *Navbar::tab_areas(area).last().unwrap();
+        assert_eq!(page, Page::Settings);
+        assert_eq!(
+            Navbar::tab_at(area, settings.x, settings.y),
+            Some(Page::Settings)
+        );
+        assert!(settings.right() <= profile.x);
+
+        assert_eq!(Navbar::tab_at(area, profile.x + 1, profile.y), None);
+
+        // too narrow for every tab: the cut off ones can't be clicked
+        let narrow = Rect::new(0, 0, 40, 3);
+        let hidden = Navbar::tab_areas(narrow);
+        assert!(hidden.last().unwrap().1.is_empty());
+        assert!(
+            hidden
+                .iter()
+                .filter(|(_, tab)| !tab.is_empty())
+                .all(|(_, tab)| tab.right() <= 39)
+        );
     }
 }
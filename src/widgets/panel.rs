@@ -0,0 +1,23 @@
+use crossterm::event::KeyEvent;
+
+use crate::widgets::sensors_widget::SensorPick;
+
+// What a panel wants the app to do after a key.
+pub enum Action {
+    PickSensor(SensorPick),
+}
+
+// A panel that can have the focus. The app routes keys it doesn't use itself
+// to whichever panel is focused.
+pub trait Panel {
+    // focused panels draw a yellow border and their key hints
+    fn set_focused(&mut self, focused: bool);
+
+    // true while the panel is in the middle of something (typed input, an
+    // open editor) and wants every key, Tab and page keys included
+    fn captures_keys(&self) -> bool {
+        false
+    }
+
+    fn handle_key_event(&mut self, key_event: KeyEvent) -> Vec<Action>;
+}
@@ -0,0 +1,133 @@
+use std::sync::Arc;
+
+use crossterm::event::{KeyCode, KeyEvent};
+use ratatui::{
+    buffer::Buffer,
+    layout::{Constraint, Rect},
+    style::{Color, Style, Stylize},
+    text::Line,
+    widgets::{
+        Block, BorderType, Borders, Cell, Paragraph, Row, StatefulWidget, Table, TableState, Widget,
+    },
+};
+
+use crate::{
+    hardware::process::ProcessInfo,
+    sampler::Snapshot,
+    widgets::panel::{Action, Panel},
+};
+
+// rows per PageUp / PageDown
+const PAGE: usize = 10;
+
+fn cpu_style(cpu: f32) -> Style {
+    match cpu {
+        cpu if cpu >= 80. => Style::new().fg(Color::Red).bold(),
+        cpu if cpu >= 25. => Style::new().fg(Color::Yellow),
+        _ => Style::new(),
+    }
+}
+
+// Busiest processes first, refreshed every couple of seconds.
+pub struct ProcessesWidget {
+    pub title: &'static str,
+    pub focused: bool,
+    // row, not pid, so the list can reorder under it
+    pub selected: usize,
+    snapshot: Arc<Snapshot>,
+}
+
+impl ProcessesWidget {
+    pub fn new(title: &'static str) -> Self {
+        Self {
+            title,
+            focused: false,
+            selected: 0,
+            snapshot: Arc::default(),
+        }
+    }
+
+    pub fn update(&mut self, snapshot: &Arc<Snapshot>) {
+        self.snapshot = snapshot.clone();
+    }
+
+    fn processes(&self) -> &[ProcessInfo] {
+        self.snapshot.processes.value.as_deref().unwrap_or_default()
+    }
+}
+
+impl Panel for ProcessesWidget {
+    fn set_focused(&mut self, focused: bool) {
+        self.focused = focused;
+    }
+
+    fn handle_key_event(&mut self, key_event: KeyEvent) -> Vec<Action> {
+        let last = self.processes().len().saturating_sub(1);
+
+        self.selected = match key_event.code {
+            KeyCode::Up => self.selected.saturating_sub(1),
+            KeyCode::Down => self.selected + 1,
+            KeyCode::PageUp => self.selected.saturating_sub(PAGE),
+            KeyCode::PageDown => self.selected + PAGE,
+            KeyCode::Home => 0,
+            KeyCode::End => last,
+            _ => self.selected,
+        }
+        .min(last);
+
+        Vec::new()
+    }
+}
+
+impl Widget for &ProcessesWidget {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        let mut block = Block::default()
+            .borders(Borders::ALL)
+            .border_type(BorderType::Rounded)
+            .title(self.title)
+            .title(Line::from(format!(" {} processes ", self.processes().len())).right_aligned());
+        if self.focused {
+            block = block
+                .border_style(Style::new().yellow())
+                .title_bottom(Line::from(" [↑/↓] [PgUp/PgDn] [Home/End] scroll ").centered());
+        }
+
+        if let Err(err) = &self.snapshot.processes.value {
+            Paragraph::new(format!("processes unavailable: {}", err).dark_gray())
+                .centered()
+                .block(block)
+                .render(area, buf);
+            return;
+        }
+
+        let rows: Vec<Row> = self
+            .processes()
+            .iter()
+            .map(|process| {
+                Row::new(vec![
+                    Cell::from(process.pid.to_string().dark_gray()),
+                    Cell::from(process.name.clone()),
+                    Cell::from(format!("{:.1}%", process.cpu)).style(cpu_style(process.cpu)),
+                    Cell::from(format!("{} MiB", process.memory / 1024 / 1024)),
+                ])
+            })
+            .collect();
+
+        let table = Table::new(
+            rows,
+            [
+                Constraint::Length(8),
+                Constraint::Fill(1),
+                Constraint::Length(8),
+                Constraint::Length(10),
+            ],
+        )
+        .header(Row::new(vec!["pid", "name", "cpu", "memory"]).bold())
+        .row_highlight_style(Style::new().reversed())
+        .block(block);
+
+        let selected = self.focused.then_some(self.selected);
+        let mut state = TableState::default().with_selected(selected);
+        StatefulWidget::render(table, area, buf, &mut state);
+    }
+}
@@ -0,0 +1,157 @@
+use std::sync::Arc;
+
+use crossterm::event::{KeyCode, KeyEvent};
+use ratatui::{
+    buffer::Buffer,
+    layout::Constraint,
+    style::{Color, Style, Stylize},
+    text::Line,
+    widgets::{Block, BorderType, Borders, Cell, Row, StatefulWidget, Table, TableState, Widget},
+};
+
+use crate::{
+    hardware::hwmon::TempSensor,
+    sampler::Snapshot,
+    widgets::panel::{Action, Panel},
+};
+
+pub enum SensorPick {
+    Auto,
+    Sensor(TempSensor),
+}
+
+// Every hwmon temperature in a table, Enter makes the selected one the "CPU"
+// temperature used by the heat bars and fan curves.
+pub struct SensorsWidget {
+    pub title: &'static str,
+    pub focused: bool,
+    pub selected: usize,
+    snapshot: Arc<Snapshot>,
+}
+
+impl SensorsWidget {
+    pub fn new(title: &'static str) -> Self {
+        Self {
+            title,
+            focused: false,
+            selected: 0,
+            snapshot: Arc::default(),
+        }
+    }
+
+    pub fn update(&mut self, snapshot: &Arc<Snapshot>) {
+        self.snapshot = snapshot.clone();
+    }
+
+    fn sensors(&self) -> &[TempSensor] {
+        self.snapshot
+            .temp_sensors
+            .value
+            .as_deref()
+            .unwrap_or_default()
+    }
+}
+
+impl Panel for SensorsWidget {
+    fn set_focused(&mut self, focused: bool) {
+        self.focused = focused;
+    }
+
+    fn handle_key_event(&mut self, key_event: KeyEvent) -> Vec<Action> {
+        let count = self.sensors().len();
+
+        let pick = match key_event.code {
+            KeyCode::Up => {
+                self.selected = self.selected.saturating_sub(1);
+                None
+            }
+            KeyCode::Down => {
+                self.selected = (self.selected + 1).min(count.saturating_sub(1));
+                None
+            }
+            KeyCode::Enter => self
+                .sensors()
+                .get(self.selected)
+                .cloned()
+                .map(SensorPick::Sensor),
+            KeyCode::Char('a') => Some(SensorPick::Auto),
+            _ => None,
+        };
+
+        pick.map(Action::PickSensor).into_iter().collect()
+    }
+}
+
+fn temp_cell(value: Option<f64>) -> Cell<'static> {
+    match value {
+        Some(value) => Cell::from(format!("{:.1}ºC", value)),
+        None => Cell::from("-".dark_gray()),
+    }
+}
+
+fn temp_style(sensor: &TempSensor) -> Style {
+    let limit = sensor.max.or(sensor.crit).unwrap_or(95.);
+
+    match sensor.input / limit {
+        ratio if ratio >= 0.95 => Style::new().fg(Color::Red).bold(),
+        ratio if ratio >= 0.8 => Style::new().fg(Color::Yellow),
+        _ => Style::new(),
+    }
+}
+
+impl Widget for &SensorsWidget {
+    fn render(self, area: ratatui::prelude::Rect, buf: &mut Buffer) {
+        let mut block = Block::default()
+            .borders(Borders::ALL)
+            .border_type(BorderType::Rounded)
+            .title(self.title);
+        if self.focused {
+            block = block.border_style(Style::new().yellow()).title_bottom(
+                Line::from(" [↑/↓] select  [Enter] use as CPU  [a] automatic ").centered(),
+            );
+        }
+
+        let cpu = self.snapshot.cpu_sensor.as_deref();
+
+        let rows: Vec<Row> = self
+            .sensors()
+            .iter()
+            .map(|sensor| {
+                let tag = match Some(sensor.id.as_str()) == cpu {
+                    true => Cell::from("CPU".yellow().bold()),
+                    false => Cell::from(""),
+                };
+
+                Row::new(vec![
+                    tag,
+                    Cell::from(sensor.chip.clone()),
+                    Cell::from(sensor.label.clone()),
+                    Cell::from(format!("{:.1}ºC", sensor.input)).style(temp_style(sensor)),
+                    temp_cell(sensor.max),
+                    temp_cell(sensor.crit),
+                    Cell::from(sensor.id.clone().dark_gray()),
+                ])
+            })
+            .collect();
+
+        let table = Table::new(
+            rows,
+            [
+                Constraint::Length(4),
+                Constraint::Length(12),
+                Constraint::Fill(1),
+                Constraint::Length(9),
+                Constraint::Length(9),
+                Constraint::Length(9),
+                Constraint::Length(14),
+            ],
+        )
+        .header(Row::new(vec!["", "chip", "sensor", "now", "max", "crit", "id"]).bold())
+        .row_highlight_style(Style::new().reversed())
+        .block(block);
+
+        let selected = self.focused.then_some(self.selected);
+        let mut state = TableState::default().with_selected(selected);
+        StatefulWidget::render(table, area, buf, &mut state);
+    }
+}
@@ -0,0 +1,103 @@
+use std::{sync::Arc, time::Duration};
+
+use ratatui::{
+    buffer::Buffer,
+    layout::{Constraint, Layout, Rect},
+    style::Stylize,
+    text::Line,
+    widgets::{Block, BorderType, Borders, Paragraph, Widget},
+};
+
+use crate::{
+    margin,
+    sampler::{Intervals, Snapshot},
+};
+
+const KEYS: &[(&str, &str)] = &[
+    ("1-7", "switch page"),
+    ("[ / ]", "previous / next page"),
+    ("Tab / Shift-Tab", "focus the next / previous panel"),
+    ("q", "quit"),
+    ("p", "cycle platform profile"),
+    ("z", "zoom charts: 1m / 1h / 24h"),
+    ("f", "fans"),
+    ("t", "temperature sensors"),
+    ("g", "governor, EPP and boost"),
+    ("b", "battery charge thresholds"),
+];
+
+fn millis(duration: Duration) -> String {
+    format!("{} ms", duration.as_millis())
+}
+
+// What the app is running with: where readings come from, how often they're
+// taken and the global keys.
+pub struct SettingsWidget {
+    pub title: &'static str,
+    intervals: Intervals,
+    snapshot: Arc<Snapshot>,
+}
+
+impl SettingsWidget {
+    pub fn new(title: &'static str, intervals: Intervals) -> Self {
+        Self {
+            title,
+            intervals,
+            snapshot: Arc::default(),
+        }
+    }
+
+    pub fn update(&mut self, snapshot: &Arc<Snapshot>) {
+        self.snapshot = snapshot.clone();
+    }
+}
+
+fn entry(name: &str, value: String) -> Line<'static> {
+    Line::from(vec![format!("  {:<22}", name).dark_gray(), value.into()])
+}
+
+impl Widget for &SettingsWidget {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        let block = Block::default()
+            .borders(Borders::ALL)
+            .border_type(BorderType::Rounded)
+            .title(self.title);
+        let inner = block.inner(area).inner(margin!(1, 1));
+        block.render(area, buf);
+
+        let [left, right] =
+            Layout::horizontal([Constraint::Fill(1), Constraint::Fill(1)]).areas(inner);
+
+        let intervals = &self.intervals;
+        let sensor = match &self.snapshot.cpu_sensor {
+            Some(id) => id.clone(),
+            None => "none found".to_string(),
+        };
+
+        let lines = vec![
+            Line::from("CPU temperature".bold()),
+            entry("sensor", sensor),
+            Line::default(),
+            Line::from("Sampling".bold()),
+            entry("fans", millis(intervals.fans)),
+            entry("temperatures", millis(intervals.temps)),
+            entry("gpu", millis(intervals.gpu)),
+            entry("memory", millis(intervals.memory)),
+            entry("cpu frequency", millis(intervals.cpu_freq)),
+            entry("cpu usage", millis(intervals.cpu_usage)),
+            entry("cpu power (RAPL)", millis(intervals.rapl)),
+            entry("cpufreq policies", millis(intervals.cpu_policy)),
+            entry("platform profile", millis(intervals.platform_profile)),
+            entry("power supply", millis(intervals.power)),
+            entry("processes", millis(intervals.processes)),
+        ];
+        Paragraph::new(lines).render(left, buf);
+
+        let mut keys = vec![Line::from("Keys".bold())];
+        keys.extend(
+            KEYS.iter()
+                .map(|(key, action)| entry(key, action.to_string())),
+        );
+        Paragraph::new(keys).render(right, buf);
+    }
+}
@@ -1,118 +1,215 @@
-use std::{
-    fs::{self},
-    path::Path,
-    process::{Command, Stdio},
-};
-
-use glob::glob;
 use ratatui::{
     buffer::Buffer,
-    layout::{Constraint, Direction, Layout},
+    layout::{Constraint, Direction, Layout, Rect},
     style::{Color, Style, Stylize},
-    symbols::Marker,
-    text::Line,
+    symbols::{self, Marker},
+    text::{Line, Span},
     widgets::{
         Axis, Bar, BarChart, BarGroup, Block, BorderType, Borders, Chart, Dataset, GraphType,
-        LegendPosition, Widget,
+        LegendPosition, LineGauge, Paragraph, Widget, Wrap,
     },
 };
 
-use crate::margin;
+use std::{sync::Arc, time::Instant};
 
-pub struct UsageWidget {
-    pub title: &'static str,
+use crate::{
+    hardware::{
+        cpufreq,
+        error::HwResult,
+        gpu::{GpuState, GpuStatus},
+        meminfo::MemInfo,
+    },
+    history::{HistoryHandle, Metric, Point, Window},
+    margin,
+    sampler::Snapshot,
+};
 
-    pub cpu_data: Vec<(f64, f64)>,
-    pub chart_x_start: f64,
-    pub chart_x_end: f64,
+// chart data out of history points, `value` picks min, avg or max
+fn line(points: &[Point], value: fn(&Point) -> f64) -> Vec<(f64, f64)> {
+    points.iter().map(|point| (point.x, value(point))).collect()
 }
 
-impl UsageWidget {
-    pub fn new(title: &'static str) -> Self {
-        Self {
-            title: title,
-            cpu_data: vec![(0., 0.)],
-            chart_x_start: 0.,
-            chart_x_end: 1.,
+fn zone_color(name: &str) -> Color {
+    match name {
+        name if name.starts_with("package") => Color::Red,
+        "core" => Color::Yellow,
+        "uncore" => Color::Cyan,
+        "dram" => Color::Magenta,
+        _ => Color::Green,
+    }
+}
+
+const USED_COLOR: Color = Color::LightRed;
+const CACHE_COLOR: Color = Color::Yellow;
+const AVAILABLE_COLOR: Color = Color::Green;
+
+fn gib(kib: u64) -> String {
+    format!("{:.1}GiB", kib as f64 / 1024.0 / 1024.0)
+}
+
+// one row of █, split into coloured segments proportional to their share of `total`
+fn stacked_bar(segments: &[(u64, Color)], total: u64, area: Rect, buf: &mut Buffer) {
+    let width = u64::from(area.width);
+    let mut x = area.x;
+    let mut acc = 0;
+
+    for &(value, color) in segments {
+        acc += value;
+        let end = area.x + (acc.min(total) * width / total.max(1)) as u16;
+        for col in x..end {
+            for row in area.top()..area.bottom() {
+                buf[(col, row)].set_symbol("█").set_fg(color);
+            }
         }
+        x = end;
     }
+}
 
-    // GPU STUFF HERE
-    pub fn is_gpu_active(&self) -> bool {
-        Command::new("sh")
-            .arg("-c")
-            .arg("nvidia-smi -L")
-            .stdout(Stdio::null())
-            .stderr(Stdio::null())
-            .status()
-            .expect("Couldnt run nvidia-smi")
-            .success()
+// used / cache / available bar with a legend, then swap, then the odd counters
+fn render_memory(mem: &MemInfo, area: Rect, buf: &mut Buffer) {
+    let [title, bar, legend, swap, extra] = Layout::vertical([
+        Constraint::Length(1),
+        Constraint::Length(1),
+        Constraint::Length(2),
+        Constraint::Length(1),
+        Constraint::Length(1),
+    ])
+    .areas(area);
+
+    Line::from(vec![
+        "RAM ".bold(),
+        format!("{} / {}", gib(mem.used()), gib(mem.total)).into(),
+    ])
+    .render(title, buf);
+
+    let free = mem.available - mem.cache();
+    stacked_bar(
+        &[
+            (mem.used(), USED_COLOR),
+            (mem.cache(), CACHE_COLOR),
+            (free, AVAILABLE_COLOR),
+        ],
+        mem.total,
+        bar,
+        buf,
+    );
+
+    // wraps onto a second line when the panel is narrow
+    let legend_line = Line::from(vec![
+        Span::styled("■ ", USED_COLOR),
+        format!("used {}  ", gib(mem.used())).into(),
+        Span::styled("■ ", CACHE_COLOR),
+        format!("cache {}  ", gib(mem.cache())).into(),
+        Span::styled("■ ", AVAILABLE_COLOR),
+        format!("available {}", gib(mem.available)).into(),
+    ]);
+    Paragraph::new(legend_line)
+        .wrap(Wrap { trim: true })
+        .render(legend, buf);
+
+    if mem.swap_total == 0 {
+        Line::from("swap: none".dark_gray()).render(swap, buf);
+    } else {
+        LineGauge::default()
+            .label(format!(
+                "swap {} / {}",
+                gib(mem.swap_used()),
+                gib(mem.swap_total)
+            ))
+            .ratio(mem.swap_used() as f64 / mem.swap_total as f64)
+            .filled_style(Style::new().fg(Color::Magenta))
+            .unfilled_style(Style::new().dark_gray())
+            .line_set(symbols::line::THICK)
+            .render(swap, buf);
     }
 
-    pub fn get_gpu_heat(&self) -> u8 {
-        if self.is_gpu_active() {
-            let output = Command::new("sh")
-                .arg("-c")
-                .arg("nvidia-smi --query-gpu=temperature.gpu --format=csv,noheader,nounits")
-                .output()
-                .expect("couldnt run nvidia-smi gpu-heat")
-                .stdout;
-
-            String::from_utf8(output)
-                .unwrap()
-                .trim()
-                .parse()
-                .expect("Couldnt parse")
-        } else {
-            // return 0 if not active,
-            // gpu heat cant be 0 anyway.
-            0
-        }
+    let mut counters = format!("dirty {}MiB", mem.dirty / 1024);
+    if mem.hugepages_total > 0 {
+        counters.push_str(&format!(
+            "  hugepages {}/{} × {}MiB",
+            mem.hugepages_total - mem.hugepages_free,
+            mem.hugepages_total,
+            mem.hugepage_size / 1024
+        ));
     }
+    Line::from(counters.dark_gray()).render(extra, buf);
+}
+
+// two lines per card: load, temperature and clock, then memory and power
+fn render_gpus(gpus: &[GpuStatus], area: Rect, buf: &mut Buffer) {
+    let mut lines: Vec<Line> = Vec::new();
 
-    // CPU STUFF HERE
-    pub fn get_cpu_temp(&self) -> u64 {
-        let temp_output = Command::new("sh")
-            .arg("-c")
-            .arg("grep k10temp /sys/class/hwmon/hwmon*/name")
-            .output()
-            .expect("Cloudnt run grep");
-
-        if temp_output.status.success() {
-            let grep_res_str = String::from_utf8_lossy(&temp_output.stdout);
-
-            // gonna return something like
-            // /sys/class/hwmon/hwmon5/name:k10temp
-            let temp_path = grep_res_str.lines().next().unwrap_or("").trim();
-
-            // This should return
-            // /sys/class/hwmon/hwmon5
-            let parent: String = Path::new(temp_path.split_once(':').unwrap().0)
-                .parent()
-                .unwrap()
-                .display()
-                .to_string();
-
-            let path = glob(&format!("{}/temp1_input", &parent))
-                .expect("failed to read glob pattern")
-                .filter_map(Result::ok)
-                .next()
-                .expect(&format!("no match cpu found also: {}", &parent));
-
-            let res_str = fs::read_to_string(&path).expect("failed to read temp_input");
-
-            if res_str.is_empty() {
-                panic!("cpu returned empty temp")
+    for gpu in gpus {
+        let name = format!("{} {} ", gpu.card.name, gpu.card.driver).bold();
+
+        let reading = match &gpu.state {
+            Ok(GpuState::Active(reading)) => reading,
+            Ok(GpuState::Suspended) => {
+                lines.push(Line::from(vec![name, "suspended".dark_gray()]));
+                lines.extend([Line::default(), Line::default()]);
+                continue;
+            }
+            Err(_) => {
+                lines.push(Line::from(vec![name, "unavailable".dark_gray()]));
+                lines.extend([Line::default(), Line::default()]);
+                continue;
             }
+        };
+
+        let mut first = vec![name];
+        if let Some(busy) = reading.busy {
+            first.push(format!("{:.0}% ", busy).into());
+        }
+        if let Some(temp) = reading.temp {
+            first.push(format!("{:.0}ºC ", temp).into());
+        }
+        if let Some(power) = reading.power {
+            first.push(format!("{:.1}W ", power).into());
+        }
+        if let Some(pstate) = &reading.pstate {
+            first.push(pstate.clone().cyan());
+        }
+
+        let mut second = vec![Span::from("  ")];
+        if let Some(sclk) = reading.sclk {
+            second.push(format!("core {}MHz ", sclk).dark_gray());
+        }
+        if let Some(mclk) = reading.mclk {
+            second.push(format!("mem {}MHz", mclk).dark_gray());
+        }
 
-            let degrees: u64 = res_str.trim().parse().expect(&format!(
-                "failed to parse the output for get_cpu_temp : {} :",
-                res_str
-            ));
-            degrees / 1000
-        } else {
-            let err_msg = String::from_utf8_lossy(&temp_output.stderr);
-            panic!("{}", err_msg);
+        let mut third = vec![Span::from("  ")];
+        if let (Some(used), Some(total)) = (reading.vram_used, reading.vram_total) {
+            third.push(format!("VRAM {}/{}MiB ", used, total).dark_gray());
+        }
+        if let Some(fan) = reading.fan {
+            third.push(format!(" fan {:.0}%", fan).dark_gray());
+        }
+
+        lines.extend([Line::from(first), Line::from(second), Line::from(third)]);
+    }
+
+    Paragraph::new(lines).render(area, buf);
+}
+
+pub struct UsageWidget {
+    pub title: &'static str,
+    snapshot: Arc<Snapshot>,
+    history: HistoryHandle,
+    // how far back the charts look
+    pub window: Window,
+    // fastest any core can go, GHz
+    pub cpu_max: f64,
+}
+
+impl UsageWidget {
+    pub fn new(title: &'static str, history: HistoryHandle) -> Self {
+        Self {
+            title,
+            snapshot: Arc::default(),
+            history,
+            window: Window::default(),
+            cpu_max: 0.,
         }
     }
 
@@ -122,55 +219,101 @@ impl UsageWidget {
         Style::new().fg(color)
     }
 
-    pub fn get_ram_usage(&self) -> (u64, u64) {
-        let ram_output = Command::new("sh")
-            .arg("-c")
-            .arg("free -m | awk 'NR==2{print $2, $3}'")
-            .output()
-            .expect("Couldnt run free -m");
-
-        let ram_output_str = String::from_utf8(ram_output.stdout).unwrap().to_string();
-
-        let str_split = ram_output_str.trim().split_once(" ").unwrap();
-        let (total, used) = (
-            str_split.0.parse::<u64>().unwrap(),
-            str_split
-                .1
-                .parse::<u64>()
-                .expect(&format!("{:?}", str_split)),
-        );
-
-        (total, used)
+    // None is a device that's powered down, not a failed read
+    pub fn heat_bar(&self, label: &'static str, temp: &HwResult<Option<u64>>) -> Bar<'_> {
+        match temp {
+            Ok(Some(temp)) => {
+                let style = self.temperature_style(*temp as u8);
+                Bar::default()
+                    .value(*temp)
+                    .label(Line::from(label))
+                    .text_value(format!(" {}ºC", temp))
+                    .style(style)
+                    .value_style(style.reversed())
+            }
+            // ratatui skips the text of 0 bars, 1 draws nothing but keeps it
+            Ok(None) => Bar::default()
+                .value(1)
+                .label(Line::from(label))
+                .text_value(" off".to_string())
+                .style(Style::new().fg(Color::Blue)),
+            Err(_) => Bar::default()
+                .value(1)
+                .label(Line::from(label))
+                .text_value(" n/a".to_string())
+                .style(Style::new().fg(Color::DarkGray)),
+        }
     }
 
-    pub fn get_cpu_speed(&self) -> (f64, f64) {
-        let output = Command::new("sh")
-            .arg("-c")
-            .arg(r#"lscpu | awk '/min MHz/ {min=$4} /max MHz/ {max=$4} END {printf "%.2f/%.2f\n", min/1000, max/1000}'"#)
-            .output()
-            .expect("Couldn't run lscpu");
-
-        let output_str = String::from_utf8(output.stdout).unwrap().to_string();
-
-        let cur_n_max = output_str.trim().split("/").collect::<Vec<_>>();
-        let (cur, max) = (
-            cur_n_max[0].parse::<f64>().expect("parsing err"),
-            cur_n_max[1].parse::<f64>().expect("parsing err"),
-        );
-        (cur, max)
+    pub fn update(&mut self, snapshot: &Arc<Snapshot>) {
+        self.snapshot = snapshot.clone();
+        if let Ok(freqs) = &snapshot.cpu_freq.value {
+            self.cpu_max = cpufreq::ceiling(freqs);
+        }
     }
 
-    pub fn update_cpu_data(&mut self) {
-        let (cur, max) = self.get_cpu_speed();
+    fn x_axis(&self) -> Axis<'static> {
+        Axis::default()
+            .style(Style::default().gray())
+            .bounds([-self.window.duration().as_secs_f64(), 0.])
+            .labels([format!("-{}", self.window.label()).bold(), "now".bold()])
+    }
+}
 
-        let new_data = (self.chart_x_end as f64, cur);
-        self.cpu_data.push(new_data);
-        if self.cpu_data.len() > 10 {
-            self.cpu_data.remove(0);
-            self.chart_x_start += 1.;
-        } else {
-            self.chart_x_end += 1.;
+impl UsageWidget {
+    fn render_power(&self, area: Rect, buf: &mut Buffer) {
+        if let Err(err) = &self.snapshot.rapl.value {
+            Paragraph::new(format!("cpu power unavailable: {}", err).dark_gray())
+                .wrap(Wrap { trim: true })
+                .render(area, buf);
+            return;
         }
+
+        // averages once zoomed out, peaks would be too jumpy to read
+        let now = Instant::now();
+        let power_data = self.history.read(|history| {
+            self.snapshot
+                .cpu_power
+                .iter()
+                .map(|zone| {
+                    let metric = Metric::Power(zone.name.clone());
+                    let points = history.points(&metric, self.window, now);
+                    (zone.name.as_str(), zone.watts, line(&points, |p| p.avg))
+                })
+                .collect::<Vec<_>>()
+        });
+
+        let max = power_data
+            .iter()
+            .flat_map(|(_, _, data)| data.iter().map(|(_, watts)| *watts))
+            .fold(10.0, f64::max)
+            .ceil();
+
+        let datasets = power_data
+            .iter()
+            .map(|(name, now, data)| {
+                Dataset::default()
+                    .name(format!("{} {:.1}W", name, now))
+                    .marker(Marker::Braille)
+                    .style(Style::default().fg(zone_color(name)))
+                    .graph_type(GraphType::Line)
+                    .data(data)
+            })
+            .collect();
+
+        Chart::new(datasets)
+            .x_axis(self.x_axis())
+            .y_axis(
+                Axis::default()
+                    .title("W")
+                    .style(Style::default().gray())
+                    .bounds([0.0, max])
+                    .labels(["0".bold(), format!("{}", max).bold()]),
+            )
+            .legend_position(Some(LegendPosition::TopLeft))
+            // the current watts are the point of this chart, keep them visible
+            .hidden_legend_constraints((Constraint::Ratio(3, 4), Constraint::Ratio(3, 4)))
+            .render(area, buf);
     }
 }
 
@@ -186,25 +329,11 @@ impl Widget for &UsageWidget {
             .border_type(BorderType::Thick);
 
         // Create the datasets to fill the chart with
-        let cpu_temp = self.get_cpu_temp();
-        let gpu_temp = self.get_gpu_heat();
-
-        let cpu_temp_style = self.temperature_style(cpu_temp as u8);
-        let gpu_temp_style = self.temperature_style(gpu_temp as u8);
+        let cpu_temp = self.snapshot.cpu_temp.value.clone().map(Some);
 
         let bars: Vec<Bar> = vec![
-            Bar::default()
-                .value(cpu_temp)
-                .label(Line::from("CPU"))
-                .text_value(format!(" {}ºC", cpu_temp))
-                .style(cpu_temp_style)
-                .value_style(cpu_temp_style.reversed()),
-            Bar::default()
-                .value(gpu_temp as u64)
-                .label(Line::from("GPU"))
-                .text_value(format!(" {}ºC", gpu_temp))
-                .style(gpu_temp_style)
-                .value_style(gpu_temp_style.reversed()),
+            self.heat_bar("CPU", &cpu_temp),
+            self.heat_bar("GPU", &self.snapshot.gpu_temp.value),
         ];
 
         main_block.render(area, buf);
@@ -214,11 +343,19 @@ impl Widget for &UsageWidget {
             .constraints(vec![Constraint::Length(20), Constraint::Fill(1)])
             .split(area.inner(margin!(2, 1)));
 
+        let gpus = self.snapshot.gpus.value.as_deref().unwrap_or_default();
+
         let second_layout = Layout::default()
             .direction(Direction::Vertical)
-            .constraints(vec![Constraint::Length(8), Constraint::Fill(1)])
+            .constraints(vec![
+                Constraint::Length(7),
+                Constraint::Length(3 * gpus.len() as u16),
+                Constraint::Fill(1),
+            ])
             .split(layout_main[1]);
 
+        render_gpus(gpus, second_layout[1].inner(margin!(1, 0)), buf);
+
         BarChart::default()
             .block(heat_block)
             .data(BarGroup::default().bars(&bars))
@@ -228,52 +365,58 @@ impl Widget for &UsageWidget {
             .direction(Direction::Vertical)
             .render(layout_main[0], buf);
 
-        let (total, used): (u64, u64) = self.get_ram_usage();
-        let _total_ram_gib = total as f64 / 1024.0;
-        let used_ram_gib = used as f64 / 1024.0;
+        match &self.snapshot.memory.value {
+            Ok(mem) => render_memory(mem, second_layout[0].inner(margin!(1, 0)), buf),
+            Err(_) => {
+                Paragraph::new("RAM: sensor unavailable".dark_gray()).render(second_layout[0], buf)
+            }
+        }
 
-        let bars: Vec<Bar> = vec![
-            Bar::default()
-                .value(used)
-                .label(Line::from("RAM"))
-                .text_value(format!("{:.1}GiB", used_ram_gib)),
-        ];
-        BarChart::default()
-            .direction(Direction::Horizontal)
-            .bar_width(5)
-            .bar_gap(2)
-            .data(BarGroup::default().bars(&bars))
-            .max(total)
-            .render(second_layout[0], buf);
-
-        let (cur, max) = self.get_cpu_speed();
-
-        let datasets = vec![
-            Dataset::default()
-                .name("Line from only 2 points".italic())
-                .marker(Marker::Braille)
-                .style(Style::default().fg(Color::Yellow))
-                .graph_type(GraphType::Line)
-                .data(&self.cpu_data),
-        ];
+        let [freq_area, power_area] =
+            Layout::vertical([Constraint::Fill(1), Constraint::Fill(1)]).areas(second_layout[2]);
+
+        let max = self.cpu_max;
+        let now = Instant::now();
+        let (avg_data, peak_data) = self.history.read(|history| {
+            let avg = history.points(&Metric::CpuFreqAvg, self.window, now);
+            let peak = history.points(&Metric::CpuFreqPeak, self.window, now);
+            (line(&avg, |p| p.avg), line(&peak, |p| p.max))
+        });
+        let datasets = match &self.snapshot.cpu_freq.value {
+            Ok(freqs) => vec![
+                Dataset::default()
+                    .name(format!("avg {:.2}GHz", cpufreq::average(freqs)))
+                    .marker(Marker::Braille)
+                    .style(Style::default().fg(Color::Yellow))
+                    .graph_type(GraphType::Line)
+                    .data(&avg_data),
+                Dataset::default()
+                    .name(format!("max {:.2}GHz", cpufreq::fastest(freqs)))
+                    .marker(Marker::Braille)
+                    .style(Style::default().fg(Color::Red))
+                    .graph_type(GraphType::Line)
+                    .data(&peak_data),
+            ],
+            Err(_) => vec![
+                Dataset::default()
+                    .name("cpu frequency unavailable".dark_gray())
+                    .data(&[]),
+            ],
+        };
 
         Chart::new(datasets)
-            .x_axis(
-                Axis::default()
-                    .title("X Axis")
-                    .style(Style::default().gray())
-                    .bounds([self.chart_x_start, self.chart_x_end])
-                    .labels(["0".bold(), "10.0".bold()]),
-            )
+            .x_axis(self.x_axis())
             .y_axis(
                 Axis::default()
-                    .title("Y Axis")
+                    .title("GHz")
                     .style(Style::default().gray())
                     .bounds([0.0, max])
-                    .labels(["0".bold(), format!("{}", max).bold()]),
+                    .labels(["0".bold(), format!("{:.1}", max).bold()]),
             )
             .legend_position(Some(LegendPosition::TopLeft))
             .hidden_legend_constraints((Constraint::Ratio(1, 2), Constraint::Ratio(1, 2)))
-            .render(second_layout[1].inner(margin!(1, 0)), buf);
+            .render(freq_area.inner(margin!(1, 0)), buf);
+
+        self.render_power(power_area.inner(margin!(1, 0)), buf);
     }
 }